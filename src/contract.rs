@@ -1,12 +1,17 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response};
 use cw2::set_contract_version;
 use semver::Version;
 
-use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
+use crate::error::{ContractError, QueryError};
+use crate::migrations::migrate_state;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RateUpdate};
+use crate::state::{
+    Config, AGGREGATIONS, CONFIG, DEVIATION_LIMITS, MAX_STALENESS, PENDING_ADMIN,
+    QUARANTINED_RATES, RATE_POLICIES,
+};
+use crate::validation::{validate_address, validate_max_rate};
 use crate::{execute, query};
 
 const CONTRACT_NAME: &str = "crates.io:milkyway-oracle";
@@ -21,8 +26,12 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    validate_max_rate(msg.max_rate)?;
     let config = Config {
-        admin_address: deps.api.addr_validate(&msg.admin_address)?,
+        admin_address: validate_address(deps.api, &msg.admin_address)?,
+        max_rate: msg.max_rate,
+        guardian: None,
+        price_source: None,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -44,39 +53,171 @@ pub fn execute(
             denom,
             purchase_rate,
             redemption_rate,
-        } => execute::post_rates(deps, env, info, denom, purchase_rate, redemption_rate),
+            slashing,
+            source_update_time,
+            source_block_height,
+        } => execute::post_rates(
+            deps,
+            env,
+            info,
+            RateUpdate {
+                denom,
+                purchase_rate,
+                redemption_rate,
+                slashing,
+                source_update_time,
+                source_block_height,
+            },
+        ),
+        ExecuteMsg::PostRatesBatch { rates } => execute::post_rates_batch(deps, env, info, rates),
+        ExecuteMsg::AddFeeder { address } => execute::add_feeder(deps, info, address),
+        ExecuteMsg::RemoveFeeder { address } => execute::remove_feeder(deps, info, address),
+        ExecuteMsg::RegisterDenom { denom, metadata } => {
+            execute::register_denom(deps, info, denom, metadata)
+        }
+        ExecuteMsg::UpdateDenom { denom, metadata } => {
+            execute::update_denom(deps, info, denom, metadata)
+        }
+        ExecuteMsg::DeregisterDenom { denom } => execute::deregister_denom(deps, info, denom),
+        ExecuteMsg::ProposeAdmin { address, expiry } => {
+            execute::propose_admin(deps, env, info, address, expiry)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute::accept_admin(deps, env, info),
+        ExecuteMsg::CancelAdminProposal {} => execute::cancel_admin_proposal(deps, info),
+        ExecuteMsg::SetMaxRate { max_rate } => execute::set_max_rate(deps, info, max_rate),
+        ExecuteMsg::SetDeviationLimits { denom, limits } => {
+            execute::set_deviation_limits(deps, info, denom, limits)
+        }
+        ExecuteMsg::SetRatePolicy { denom, policy } => {
+            execute::set_rate_policy(deps, info, denom, policy)
+        }
+        ExecuteMsg::SetMaxStaleness {
+            denom,
+            max_staleness_seconds,
+        } => execute::set_max_staleness(deps, info, denom, max_staleness_seconds),
+        ExecuteMsg::SetRetention { denom, retention } => {
+            execute::set_retention(deps, env, info, denom, retention)
+        }
+        ExecuteMsg::ConfirmQuarantinedRates { denom } => {
+            execute::confirm_quarantined_rates(deps, env, info, denom)
+        }
+        ExecuteMsg::RejectQuarantinedRates { denom } => {
+            execute::reject_quarantined_rates(deps, info, denom)
+        }
+        ExecuteMsg::SetAggregation { denom, aggregation } => {
+            execute::set_aggregation(deps, info, denom, aggregation)
+        }
+        ExecuteMsg::Pause { denom } => execute::pause(deps, info, denom),
+        ExecuteMsg::Unpause { denom } => execute::unpause(deps, info, denom),
+        ExecuteMsg::SetGuardian { address } => execute::set_guardian(deps, info, address),
+        ExecuteMsg::SetPriceSource { price_source } => {
+            execute::set_price_source(deps, info, price_source)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> Result<Binary, QueryError> {
+    Ok(match msg {
         QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::PendingAdmin {} => to_json_binary(&PENDING_ADMIN.may_load(deps.storage)?),
+        QueryMsg::Feeders {} => to_json_binary(&query::query_feeders(deps)?),
+        QueryMsg::Denoms { start_after, limit } => {
+            to_json_binary(&query::query_denoms(deps, start_after, limit)?)
+        }
+        QueryMsg::DeviationLimits { denom } => {
+            to_json_binary(&DEVIATION_LIMITS.may_load(deps.storage, &denom)?)
+        }
+        QueryMsg::MaxStaleness { denom } => {
+            to_json_binary(&MAX_STALENESS.may_load(deps.storage, &denom)?)
+        }
+        QueryMsg::Retention { denom } => to_json_binary(&query::query_retention(deps, denom)?),
+        QueryMsg::RatePolicy { denom } => {
+            to_json_binary(&RATE_POLICIES.may_load(deps.storage, &denom)?)
+        }
+        QueryMsg::QuarantinedRates { denom } => {
+            to_json_binary(&QUARANTINED_RATES.may_load(deps.storage, &denom)?)
+        }
+        QueryMsg::Paused { denom } => to_json_binary(&query::query_paused(deps, denom)?),
+        QueryMsg::Rates { denom } => to_json_binary(&query::query_rates(deps, denom)?),
+        QueryMsg::RedemptionRates { denoms } => {
+            to_json_binary(&query::query_redemption_rates(deps, env, denoms)?)
+        }
+        QueryMsg::AllLatestRates { start_after, limit } => to_json_binary(
+            &query::query_all_latest_rates(deps, env, start_after, limit)?,
+        ),
+        QueryMsg::Price { denom } => to_json_binary(&query::query_price(deps, env, denom)?),
+        QueryMsg::Aggregation { denom } => {
+            to_json_binary(&AGGREGATIONS.may_load(deps.storage, &denom)?)
+        }
+        QueryMsg::AggregationRound { denom, round } => {
+            to_json_binary(&query::query_aggregation_round(deps, denom, round)?)
+        }
         QueryMsg::RedemptionRate { denom, params } => {
-            to_json_binary(&query::query_redemption_rate(deps, denom, params)?)
+            to_json_binary(&query::query_redemption_rate(deps, env, denom, params)?)
+        }
+        QueryMsg::RedemptionRateTwap {
+            denom,
+            window_seconds,
+        } => to_json_binary(&query::query_redemption_rate_twap(
+            deps,
+            env,
+            denom,
+            window_seconds,
+        )?),
+        QueryMsg::RedemptionRateAt { denom, at } => {
+            to_json_binary(&query::query_redemption_rate_at(deps, denom, at)?)
         }
         QueryMsg::HistoricalRedemptionRates {
             denom,
             params,
+            start_after,
             limit,
+            order,
         } => to_json_binary(&query::query_historical_redemption_rates(
-            deps, denom, params, limit,
+            deps,
+            env,
+            denom,
+            params,
+            start_after,
+            limit,
+            order,
         )?),
         QueryMsg::PurchaseRate { denom, params } => {
-            to_json_binary(&query::query_purchase_rate(deps, denom, params)?)
+            to_json_binary(&query::query_purchase_rate(deps, env, denom, params)?)
+        }
+        QueryMsg::PurchaseRateTwap {
+            denom,
+            window_seconds,
+        } => to_json_binary(&query::query_purchase_rate_twap(
+            deps,
+            env,
+            denom,
+            window_seconds,
+        )?),
+        QueryMsg::PurchaseRateAt { denom, at } => {
+            to_json_binary(&query::query_purchase_rate_at(deps, denom, at)?)
         }
         QueryMsg::HistoricalPurchaseRates {
             denom,
             params,
+            start_after,
             limit,
+            order,
         } => to_json_binary(&query::query_historical_purchase_rates(
-            deps, denom, params, limit,
+            deps,
+            env,
+            denom,
+            params,
+            start_after,
+            limit,
+            order,
         )?),
-    }
+    }?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
     let current_version = cw2::get_contract_version(deps.storage)?;
     if current_version.contract != CONTRACT_NAME {
         return Err(ContractError::InvalidContract {});
@@ -100,30 +241,48 @@ pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, C
     }
 
     // migrate data
-    // none
+    migrate_state(deps.branch(), &version, msg)?;
 
     // set new contract version
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    Ok(Response::new())
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", version.to_string())
+        .add_attribute("to_version", new_version.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::marker::PhantomData;
     use std::str::FromStr;
 
-    use crate::contract::{execute, instantiate, query, migrate};
+    use crate::contract::{execute, instantiate, migrate, query};
+    use crate::error::{ContractError, QueryError};
+    use crate::migrations::v1_0_0;
     use crate::msg::{
-        MigrateMsg, ExecuteMsg, HistoricalPurchaseRatesResponse, HistoricalRedemptionRatesResponse,
-        InstantiateMsg, PurchaseRate, PurchaseRateResponse, 
-        QueryMsg, RedemptionRateResponse, RedemptionRate,
+        DenomInfo, DenomsResponse, ExecuteMsg, FeedersResponse, HistoricalPurchaseRatesResponse,
+        HistoricalRedemptionRatesResponse, InstantiateMsg, LatestRates, LatestRatesResponse,
+        MigrateMsg, OnStale, OrderBy, PointInTime, PriceResponse, PriceSourceInfo,
+        PriceSourceQueryMsg, PriceSourceResponse, PurchaseRate, PurchaseRateResponse,
+        PurchaseRateTwapResponse, QueryMsg, RateQueryParams, RateQueryParamsV1, RateUpdate,
+        RedemptionRate, RedemptionRateResponse, RedemptionRateTwapResponse,
+    };
+    use crate::state::{
+        Aggregation, AggregationRound, Config, DenomMetadata, DeviationLimits, PendingAdmin,
+        QuarantinedRates, RateOrigin, RatePolicy, Rates, Retention, Submission,
     };
-    use crate::state::Config;
     use cosmwasm_std::testing::{
         mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
     };
-    use cosmwasm_std::{attr, from_json, to_json_binary, Decimal, Empty, Env, MessageInfo, OwnedDeps};
+    use cosmwasm_std::{
+        attr, from_json, to_json_binary, Addr, Binary, ContractResult, Decimal, Empty, Env, Event,
+        MessageInfo, Order, OwnedDeps, Record, Storage, SystemError, SystemResult, WasmQuery,
+    };
+    use cw2::set_contract_version;
 
     const ADMIN_ADDRESS: &str = "my_address";
+    const FEEDER_ADDRESS: &str = "feeder_address";
 
     fn default_mock() -> (
         OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
@@ -147,6 +306,7 @@ mod tests {
 
         let msg = InstantiateMsg {
             admin_address: ADMIN_ADDRESS.to_string(),
+            max_rate: None,
         };
 
         let resp = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
@@ -160,6 +320,61 @@ mod tests {
         (deps, env, info)
     }
 
+    // instantiate, register the feeder and the default denom, returns the feeder's info
+    fn default_instantiate_with_feeder() -> (
+        OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>,
+        Env,
+        MessageInfo,
+    ) {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::AddFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: "factory/denom".to_string(),
+            metadata: denom_metadata(),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        (deps, env, mock_info(FEEDER_ADDRESS, &[]))
+    }
+
+    // seed the state as stored by v1.0.0, without any rates
+    fn v1_0_0_mock() -> (OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>, Env) {
+        let (mut deps, env, _info) = default_mock();
+        set_contract_version(deps.as_mut().storage, "crates.io:milkyway-oracle", "1.0.0").unwrap();
+        let legacy_config = v1_0_0::Config {
+            admin_address: Addr::unchecked(ADMIN_ADDRESS),
+        };
+        v1_0_0::CONFIG
+            .save(deps.as_mut().storage, &legacy_config)
+            .unwrap();
+        (deps, env)
+    }
+
+    fn denom_metadata() -> DenomMetadata {
+        DenomMetadata {
+            base_denom: "utia".to_string(),
+            host_chain_id: "celestia".to_string(),
+            decimals: 6,
+            display_symbol: "milkTIA".to_string(),
+        }
+    }
+
+    fn post_rates_msg(denom: &str, purchase_rate: &str, redemption_rate: &str) -> ExecuteMsg {
+        ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some(purchase_rate.to_string()),
+            redemption_rate: Some(redemption_rate.to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        }
+    }
+
     #[test]
     fn test_config() {
         let (deps, env, _info) = default_instantiate();
@@ -182,14 +397,26 @@ mod tests {
             params: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: purchase rate not found");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::NotFound {
+                denom: denom.to_string(),
+                rate: "purchase rate".to_string()
+            }
+        );
 
         let msg = QueryMsg::RedemptionRate {
             denom: denom.to_string(),
             params: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: redemption rate not found");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::NotFound {
+                denom: denom.to_string(),
+                rate: "redemption rate".to_string()
+            }
+        );
     }
 
     #[test]
@@ -202,43 +429,70 @@ mod tests {
             params: Some(to_json_binary("test").unwrap()),
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: invalid query request - params must be None");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::InvalidParams {
+                reason: "params must be None or valid rate query params".to_string()
+            }
+        );
 
         let msg = QueryMsg::RedemptionRate {
             denom: denom.to_string(),
             params: Some(to_json_binary("test").unwrap()),
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: invalid query request - params must be None");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::InvalidParams {
+                reason: "params must be None or valid rate query params".to_string()
+            }
+        );
        
         let msg = QueryMsg::HistoricalPurchaseRates {
             denom: denom.to_string(),
             params: Some(to_json_binary("test").unwrap()),
+            start_after: None,
             limit: None,
+            order: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: invalid query request - params must be None");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::InvalidParams {
+                reason: "params must be None or valid rate query params".to_string()
+            }
+        );
    
         let msg = QueryMsg::HistoricalRedemptionRates {
             denom: denom.to_string(),
             params: Some(to_json_binary("test").unwrap()),
+            start_after: None,
             limit: None,
+            order: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg);
-        assert_eq!(resp.unwrap_err().to_string(), "Generic error: invalid query request - params must be None");
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::InvalidParams {
+                reason: "params must be None or valid rate query params".to_string()
+            }
+        );
     }
 
     #[test]
     fn test_post_rates() {
         // Instantiate contract
-        let (mut deps, env, info) = default_instantiate();
+        let (mut deps, env, info) = default_instantiate_with_feeder();
         let denom = "factory/denom";
 
         // Post rates
         let msg = ExecuteMsg::PostRates {
             denom: denom.to_string(),
-            purchase_rate: "0.9".to_string(),
-            redemption_rate: "1.1".to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
         };
         let resp = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
         assert_eq!(
@@ -249,6 +503,7 @@ mod tests {
                 attr("purchase_rate", "0.9"),
                 attr("redemption_rate", "1.1"),
                 attr("update_time", "1571797419"),
+                attr("slashing", "false"),
             ]
         );
 
@@ -263,6 +518,12 @@ mod tests {
             PurchaseRateResponse {
                 purchase_rate: Decimal::from_str("0.9").unwrap(),
                 update_time: 1571797419,
+                block_height: 12345,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: false,
+                is_frozen: false,
             }
         );
 
@@ -277,6 +538,12 @@ mod tests {
             RedemptionRateResponse {
                 redemption_rate: Decimal::from_str("1.1").unwrap(),
                 update_time: 1571797419,
+                block_height: 12345,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: false,
+                is_frozen: false,
             }
         );
     }
@@ -284,14 +551,17 @@ mod tests {
     #[test]
     fn test_historical_post_rates() {
         // Instantiate contract
-        let (mut deps, mut env, info) = default_instantiate();
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
         let denom = "factory/denom";
 
         // Post rates
         let msg = ExecuteMsg::PostRates {
             denom: denom.to_string(),
-            purchase_rate: "0.9".to_string(),
-            redemption_rate: "1.1".to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
         };
         execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -301,8 +571,11 @@ mod tests {
         // Post rates again
         let msg = ExecuteMsg::PostRates {
             denom: denom.to_string(),
-            purchase_rate: "0.8".to_string(),
-            redemption_rate: "1.2".to_string(),
+            purchase_rate: Some("0.8".to_string()),
+            redemption_rate: Some("1.2".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
         };
         execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -317,13 +590,21 @@ mod tests {
             PurchaseRateResponse {
                 purchase_rate: Decimal::from_str("0.8").unwrap(),
                 update_time: 1571797469,
+                block_height: 12355,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: false,
+                is_frozen: false,
             }
         );
 
         let msg = QueryMsg::HistoricalPurchaseRates {
             denom: denom.to_string(),
             params: None,
+            start_after: None,
             limit: None,
+            order: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
         let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
@@ -335,13 +616,22 @@ mod tests {
                         denom: denom.to_string(),
                         purchase_rate: Decimal::from_str("0.8").unwrap(),
                         update_time: 1571797469,
+                        block_height: 12355,
+                        feeder: Addr::unchecked(FEEDER_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
                     },
                     PurchaseRate {
                         denom: denom.to_string(),
                         purchase_rate: Decimal::from_str("0.9").unwrap(),
                         update_time: 1571797419,
+                        block_height: 12345,
+                        feeder: Addr::unchecked(FEEDER_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
                     },
-                ]
+                ],
+                next_key: None,
             }
         );
 
@@ -356,13 +646,21 @@ mod tests {
             RedemptionRateResponse {
                 redemption_rate: Decimal::from_str("1.2").unwrap(),
                 update_time: 1571797469,
+                block_height: 12355,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: false,
+                is_frozen: false,
             }
         );
 
         let msg = QueryMsg::HistoricalRedemptionRates {
             denom: denom.to_string(),
             params: None,
+            start_after: None,
             limit: None,
+            order: None,
         };
         let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
         let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
@@ -374,14 +672,3199 @@ mod tests {
                         denom: denom.to_string(),
                         redemption_rate: Decimal::from_str("1.2").unwrap(),
                         update_time: 1571797469,
+                        block_height: 12355,
+                        feeder: Addr::unchecked(FEEDER_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
+                        slashing: false,
                     },
                     RedemptionRate {
                         denom: denom.to_string(),
                         redemption_rate: Decimal::from_str("1.1").unwrap(),
                         update_time: 1571797419,
+                        block_height: 12345,
+                        feeder: Addr::unchecked(FEEDER_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
+                        slashing: false,
                     },
-                ]
+                ],
+                next_key: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_feeders() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::AddFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "add_feeder"), attr("feeder", FEEDER_ADDRESS)]
+        );
+
+        // Adding the same feeder twice fails
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FeederAlreadyRegistered {
+                address: FEEDER_ADDRESS.to_string()
+            }
+        );
+
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::Feeders {}).unwrap();
+        let resp: FeedersResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            FeedersResponse {
+                feeders: vec![Addr::unchecked(FEEDER_ADDRESS)]
+            }
+        );
+
+        let msg = ExecuteMsg::RemoveFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "remove_feeder"),
+                attr("feeder", FEEDER_ADDRESS)
+            ]
+        );
+
+        // Removing an unknown feeder fails
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FeederNotRegistered {
+                address: FEEDER_ADDRESS.to_string()
+            }
+        );
+
+        let resp = query(deps.as_ref(), env, QueryMsg::Feeders {}).unwrap();
+        let resp: FeedersResponse = from_json(&resp).unwrap();
+        assert_eq!(resp, FeedersResponse { feeders: vec![] });
+    }
+
+    #[test]
+    fn test_manage_feeders_unauthorized() {
+        let (mut deps, env, _info) = default_instantiate_with_feeder();
+
+        let msg = ExecuteMsg::AddFeeder {
+            address: "other_feeder".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(FEEDER_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::RemoveFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info(FEEDER_ADDRESS, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_post_rates_unauthorized() {
+        let (mut deps, env, _info) = default_instantiate_with_feeder();
+
+        // The admin is not a feeder
+        let msg = ExecuteMsg::PostRates {
+            denom: "factory/denom".to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // A removed feeder can no longer post
+        let remove = ExecuteMsg::RemoveFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            remove,
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env, mock_info(FEEDER_ADDRESS, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_admin_transfer() {
+        let (mut deps, env, info) = default_instantiate();
+        let new_admin = "new_admin";
+
+        let msg = ExecuteMsg::ProposeAdmin {
+            address: new_admin.to_string(),
+            expiry: None,
+        };
+        let resp = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "propose_admin"),
+                attr("pending_admin", new_admin),
+                attr("expiry", "none"),
+            ]
+        );
+
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap();
+        let resp: Option<PendingAdmin> = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            Some(PendingAdmin {
+                address: Addr::unchecked(new_admin),
+                expiry: None,
+            })
+        );
+
+        // Only the pending admin can accept
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("someone_else", &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(new_admin, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "accept_admin"),
+                attr("admin_address", new_admin),
+            ]
+        );
+
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let resp: Config = from_json(&resp).unwrap();
+        assert_eq!(resp.admin_address, new_admin.to_string());
+
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap();
+        let resp: Option<PendingAdmin> = from_json(&resp).unwrap();
+        assert_eq!(resp, None);
+
+        // The old admin lost its permissions
+        let msg = ExecuteMsg::AddFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        let err = execute(deps.as_mut(), env, mock_info(ADMIN_ADDRESS, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_admin_transfer_expiry() {
+        let (mut deps, mut env, info) = default_instantiate();
+        let new_admin = "new_admin";
+        let now = env.block.time.seconds();
+
+        // The expiry must be in the future
+        let msg = ExecuteMsg::ProposeAdmin {
+            address: new_admin.to_string(),
+            expiry: Some(now),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidExpiry { expiry: now });
+
+        let msg = ExecuteMsg::ProposeAdmin {
+            address: new_admin.to_string(),
+            expiry: Some(now + 100),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100);
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info(new_admin, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AdminProposalExpired {});
+    }
+
+    #[test]
+    fn test_cancel_admin_proposal() {
+        let (mut deps, env, info) = default_instantiate();
+        let new_admin = "new_admin";
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::CancelAdminProposal {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdmin {});
+
+        let msg = ExecuteMsg::ProposeAdmin {
+            address: new_admin.to_string(),
+            expiry: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Only the admin can cancel
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(new_admin, &[]),
+            ExecuteMsg::CancelAdminProposal {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::CancelAdminProposal {},
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            mock_info(new_admin, &[]),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoPendingAdmin {});
+    }
+
+    #[test]
+    fn test_post_rates_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("not_a_rate".to_string()),
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidRate {
+                field: "purchase_rate".to_string(),
+                value: "not_a_rate".to_string(),
+            }
+        );
+
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("-1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidRate {
+                field: "redemption_rate".to_string(),
+                value: "-1".to_string(),
+            }
+        );
+
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0".to_string()),
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ZeroRate {
+                field: "purchase_rate".to_string(),
+            }
+        );
+
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("0.0".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ZeroRate {
+                field: "redemption_rate".to_string(),
+            }
+        );
+
+        // Nothing has been stored
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_post_rates_above_ceiling() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = ExecuteMsg::SetMaxRate {
+            max_rate: Some(Decimal::from_str("2").unwrap()),
+        };
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "set_max_rate"), attr("max_rate", "2")]
+        );
+
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("2.5".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RateAboveCeiling {
+                field: "redemption_rate".to_string(),
+                value: Decimal::from_str("2.5").unwrap(),
+                max_rate: Decimal::from_str("2").unwrap(),
+            }
+        );
+
+        // The ceiling itself is accepted
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("2".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // Removing the ceiling accepts any rate
+        let msg = ExecuteMsg::SetMaxRate { max_rate: None };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        env.block.height += 1;
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("2.5".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_set_max_rate_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+
+        let msg = ExecuteMsg::SetMaxRate {
+            max_rate: Some(Decimal::zero()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ZeroRate {
+                field: "max_rate".to_string(),
             }
         );
+
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_deviation_per_update() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+
+        let limits = DeviationLimits {
+            max_change_per_update: Some(Decimal::percent(10)),
+            max_change_per_window: None,
+            window_seconds: 0,
+        };
+        let msg = ExecuteMsg::SetDeviationLimits {
+            denom: denom.to_string(),
+            limits: Some(limits.clone()),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::DeviationLimits {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<DeviationLimits> = from_json(&resp).unwrap();
+        assert_eq!(resp, Some(limits));
+
+        // The first rates are always accepted
+        let msg = post_rates_msg(denom, "0.9", "1.05");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A change within the limits is accepted
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(5);
+        let msg = post_rates_msg(denom, "0.95", "1.1");
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(resp.attributes[0], attr("action", "post_rates"));
+
+        // An out-of-band change is quarantined
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(5);
+        let msg = post_rates_msg(denom, "0.95", "1000");
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "quarantine_rates"), attr("denom", denom)]
+        );
+        assert_eq!(
+            resp.events,
+            vec![Event::new("rates_quarantined")
+                .add_attribute("denom", denom)
+                .add_attribute("purchase_rate", "0.95")
+                .add_attribute("redemption_rate", "1000")
+                .add_attribute("update_time", "1571797429")
+                .add_attribute("feeder", FEEDER_ADDRESS)
+                .add_attribute("limit", "max_change_per_update")]
+        );
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.1").unwrap());
+
+        let msg = QueryMsg::QuarantinedRates {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<QuarantinedRates> = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            Some(QuarantinedRates {
+                rates: Rates {
+                    purchase_rate: Decimal::from_str("0.95").unwrap(),
+                    redemption_rate: Decimal::from_str("1000").unwrap(),
+                    update_time: 1571797429,
+                    block_height: env.block.height,
+                    feeder: Addr::unchecked(FEEDER_ADDRESS),
+                    source_update_time: None,
+                    source_block_height: None,
+                    slashing: false,
+                    purchase_rate_carried_from: None,
+                    redemption_rate_carried_from: None,
+                },
+            })
+        );
+
+        // Only the admin can force the rates through
+        let msg = ExecuteMsg::ConfirmQuarantinedRates {
+            denom: denom.to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let resp = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "confirm_quarantined_rates"),
+                attr("denom", denom),
+                attr("purchase_rate", "0.95"),
+                attr("redemption_rate", "1000"),
+                attr("update_time", "1571797439"),
+                attr("posted_time", "1571797429"),
+            ]
+        );
+
+        let msg_query = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_query).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1000").unwrap());
+        // Effective from the confirmation, still attributed to the feeder
+        assert_eq!(resp.block_height, env.block.height);
+        assert_eq!(resp.update_time, 1571797439);
+        assert_eq!(resp.feeder, Addr::unchecked(FEEDER_ADDRESS));
+
+        // The previous rate stays in effect until the confirmation
+        let msg_query = QueryMsg::RedemptionRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Time(1571797434),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_query).unwrap();
+        let resp: RedemptionRate = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.1").unwrap());
+
+        let msg_query = QueryMsg::RedemptionRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 20,
+        };
+        let mut later_env = env.clone();
+        later_env.block.time = later_env.block.time.plus_seconds(10);
+        let resp = query(deps.as_ref(), later_env, msg_query).unwrap();
+        let resp: RedemptionRateTwapResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("500.55").unwrap());
+
+        let err = execute(deps.as_mut(), env, admin_info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::NoQuarantinedRates {
+                denom: denom.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_deviation_per_window() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+
+        let msg = ExecuteMsg::SetDeviationLimits {
+            denom: denom.to_string(),
+            limits: Some(DeviationLimits {
+                max_change_per_update: Some(Decimal::percent(10)),
+                max_change_per_window: Some(Decimal::percent(15)),
+                window_seconds: 100,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = post_rates_msg(denom, "1", "1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(50);
+        let msg = post_rates_msg(denom, "1.1", "1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // 10% over the latest rates but 21% over the rates posted 50 seconds ago
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(50);
+        let msg = post_rates_msg(denom, "1.21", "1");
+        let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(resp.events[0].ty, "rates_quarantined");
+        assert_eq!(
+            resp.events[0].attributes.last().unwrap(),
+            attr("limit", "max_change_per_window")
+        );
+
+        // The admin can discard the quarantined rates
+        let reject = ExecuteMsg::RejectQuarantinedRates {
+            denom: denom.to_string(),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), admin_info, reject).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "reject_quarantined_rates"),
+                attr("denom", denom)
+            ]
+        );
+        let msg_query = QueryMsg::QuarantinedRates {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_query).unwrap();
+        let resp: Option<QuarantinedRates> = from_json(&resp).unwrap();
+        assert_eq!(resp, None);
+
+        // Once the first rates left the window the same change is accepted
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(1);
+        let resp = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(resp.attributes[0], attr("action", "post_rates"));
+    }
+
+    #[test]
+    fn test_set_deviation_limits_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+
+        let msg = ExecuteMsg::SetDeviationLimits {
+            denom: "factory/denom".to_string(),
+            limits: Some(DeviationLimits {
+                max_change_per_update: None,
+                max_change_per_window: Some(Decimal::percent(15)),
+                window_seconds: 0,
+            }),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidDeviationLimits {});
+
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn test_non_decreasing_redemption_rate() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+
+        let policy = RatePolicy {
+            non_decreasing_redemption_rate: true,
+        };
+        let msg = ExecuteMsg::SetRatePolicy {
+            denom: denom.to_string(),
+            policy: Some(policy.clone()),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::RatePolicy {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<RatePolicy> = from_json(&resp).unwrap();
+        assert_eq!(resp, Some(policy));
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A decrease without a slashing flag is rejected
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg(denom, "0.9", "1.05");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::RedemptionRateDecreased {
+                denom: denom.to_string(),
+                previous: Decimal::from_str("1.1").unwrap(),
+                current: Decimal::from_str("1.05").unwrap(),
+            }
+        );
+
+        // Feeders can't flag slashing events
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("1.05".to_string()),
+            slashing: Some(true),
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Slashing events must post the slashed redemption rate
+        let msg_without_redemption_rate = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: None,
+            slashing: Some(true),
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            msg_without_redemption_rate,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SlashingWithoutRedemptionRate {
+                denom: denom.to_string()
+            }
+        );
+
+        let resp = execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+        assert_eq!(resp.attributes.last().unwrap(), attr("slashing", "true"));
+
+        // The slashing flag stays with the redemption rate it was posted with
+        let mut later_env = env.clone();
+        later_env.block.height += 1;
+        later_env.block.time = later_env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.95".to_string()),
+            redemption_rate: None,
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        execute(
+            deps.as_mut(),
+            later_env.clone(),
+            mock_info(FEEDER_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = QueryMsg::RedemptionRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Height(later_env.block.height),
+        };
+        let resp = query(deps.as_ref(), later_env, msg).unwrap();
+        let resp: RedemptionRate = from_json(&resp).unwrap();
+        assert_eq!(resp.block_height, 12346);
+        assert!(resp.slashing);
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            HistoricalRedemptionRatesResponse {
+                redemption_rates: vec![
+                    RedemptionRate {
+                        denom: denom.to_string(),
+                        redemption_rate: Decimal::from_str("1.05").unwrap(),
+                        update_time: 1571797429,
+                        block_height: 12346,
+                        feeder: Addr::unchecked(ADMIN_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
+                        slashing: true,
+                    },
+                    RedemptionRate {
+                        denom: denom.to_string(),
+                        redemption_rate: Decimal::from_str("1.1").unwrap(),
+                        update_time: 1571797419,
+                        block_height: 12345,
+                        feeder: Addr::unchecked(FEEDER_ADDRESS),
+                        source_update_time: None,
+                        source_block_height: None,
+                        slashing: false,
+                    },
+                ],
+                next_key: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_decreasing_redemption_rate_without_policy() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        env.block.height += 1;
+        let msg = post_rates_msg(denom, "0.9", "1.05");
+        execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_stale_rates() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = ExecuteMsg::SetMaxStaleness {
+            denom: denom.to_string(),
+            max_staleness_seconds: Some(3600),
+        };
+        let resp = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "set_max_staleness"),
+                attr("denom", denom),
+                attr("max_staleness_seconds", "3600"),
+            ]
+        );
+
+        let msg = QueryMsg::MaxStaleness {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<u64> = from_json(&resp).unwrap();
+        assert_eq!(resp, Some(3600));
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Exactly at the maximum staleness the rates are still fresh
+        env.block.time = env.block.time.plus_seconds(3600);
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateResponse = from_json(&resp).unwrap();
+        assert!(!resp.is_stale);
+
+        env.block.time = env.block.time.plus_seconds(1);
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            PurchaseRateResponse {
+                purchase_rate: Decimal::from_str("0.9").unwrap(),
+                update_time: 1571797419,
+                block_height: 12345,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: true,
+                is_frozen: false,
+            }
+        );
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            RedemptionRateResponse {
+                redemption_rate: Decimal::from_str("1.1").unwrap(),
+                update_time: 1571797419,
+                block_height: 12345,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                is_stale: true,
+                is_frozen: false,
+            }
+        );
+
+        // Without a maximum staleness the rates are never stale
+        let msg = ExecuteMsg::SetMaxStaleness {
+            denom: denom.to_string(),
+            max_staleness_seconds: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert!(!resp.is_stale);
+    }
+
+    #[test]
+    fn test_denom_registry() {
+        let (mut deps, env, info) = default_instantiate();
+
+        for denom in ["factory/b", "factory/a", "factory/c"] {
+            let msg = ExecuteMsg::RegisterDenom {
+                denom: denom.to_string(),
+                metadata: denom_metadata(),
+            };
+            let resp = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            assert_eq!(
+                resp.attributes,
+                vec![attr("action", "register_denom"), attr("denom", denom)]
+            );
+        }
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: "factory/a".to_string(),
+            metadata: denom_metadata(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomAlreadyRegistered {
+                denom: "factory/a".to_string()
+            }
+        );
+
+        let metadata = DenomMetadata {
+            decimals: 18,
+            ..denom_metadata()
+        };
+        let msg = ExecuteMsg::UpdateDenom {
+            denom: "factory/b".to_string(),
+            metadata: metadata.clone(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::Denoms {
+            start_after: None,
+            limit: Some(2),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: DenomsResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            DenomsResponse {
+                denoms: vec![
+                    DenomInfo {
+                        denom: "factory/a".to_string(),
+                        metadata: denom_metadata(),
+                    },
+                    DenomInfo {
+                        denom: "factory/b".to_string(),
+                        metadata,
+                    },
+                ],
+            }
+        );
+
+        let msg = ExecuteMsg::DeregisterDenom {
+            denom: "factory/c".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomNotRegistered {
+                denom: "factory/c".to_string()
+            }
+        );
+
+        let msg = QueryMsg::Denoms {
+            start_after: Some("factory/a".to_string()),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: DenomsResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.denoms.len(), 1);
+        assert_eq!(resp.denoms[0].denom, "factory/b");
+    }
+
+    #[test]
+    fn test_denom_registry_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: "factory/other".to_string(),
+            metadata: DenomMetadata {
+                host_chain_id: " ".to_string(),
+                ..denom_metadata()
+            },
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidDenomMetadata {
+                field: "host_chain_id".to_string()
+            }
+        );
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: "factory/other".to_string(),
+            metadata: denom_metadata(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // Rates for unregistered denoms are rejected
+        let msg = post_rates_msg("factory/other", "0.9", "1.1");
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomNotRegistered {
+                denom: "factory/other".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_post_rates_batch() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+        let other_denom = "factory/other";
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: other_denom.to_string(),
+            metadata: denom_metadata(),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::PostRatesBatch {
+            rates: vec![
+                RateUpdate {
+                    denom: "factory/denom".to_string(),
+                    purchase_rate: Some("0.9".to_string()),
+                    redemption_rate: Some("1.1".to_string()),
+                    slashing: None,
+                    source_update_time: None,
+                    source_block_height: None,
+                },
+                RateUpdate {
+                    denom: other_denom.to_string(),
+                    purchase_rate: Some("0.8".to_string()),
+                    redemption_rate: Some("1.2".to_string()),
+                    slashing: None,
+                    source_update_time: None,
+                    source_block_height: None,
+                },
+            ],
+        };
+        let resp = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "post_rates_batch"), attr("count", "2")]
+        );
+        assert_eq!(
+            resp.events,
+            vec![
+                Event::new("post_rates").add_attributes(vec![
+                    attr("denom", "factory/denom"),
+                    attr("purchase_rate", "0.9"),
+                    attr("redemption_rate", "1.1"),
+                    attr("update_time", "1571797419"),
+                    attr("slashing", "false"),
+                ]),
+                Event::new("post_rates").add_attributes(vec![
+                    attr("denom", other_denom),
+                    attr("purchase_rate", "0.8"),
+                    attr("redemption_rate", "1.2"),
+                    attr("update_time", "1571797419"),
+                    attr("slashing", "false"),
+                ]),
+            ]
+        );
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: other_denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.2").unwrap());
+    }
+
+    #[test]
+    fn test_post_rates_batch_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+
+        let msg = ExecuteMsg::PostRatesBatch { rates: vec![] };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::EmptyBatch {});
+
+        // A single invalid entry fails the whole batch
+        let msg = ExecuteMsg::PostRatesBatch {
+            rates: vec![
+                RateUpdate {
+                    denom: "factory/denom".to_string(),
+                    purchase_rate: Some("0.9".to_string()),
+                    redemption_rate: Some("1.1".to_string()),
+                    slashing: None,
+                    source_update_time: None,
+                    source_block_height: None,
+                },
+                RateUpdate {
+                    denom: "factory/unknown".to_string(),
+                    purchase_rate: Some("0.8".to_string()),
+                    redemption_rate: Some("1.2".to_string()),
+                    slashing: None,
+                    source_update_time: None,
+                    source_block_height: None,
+                },
+            ],
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomNotRegistered {
+                denom: "factory/unknown".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_historical_rates_pagination() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+        let first_height = env.block.height;
+
+        for i in 1..=5 {
+            let msg = post_rates_msg(denom, "0.9", &format!("1.{}", i));
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            env.block.height += 1;
+            env.block.time = env.block.time.plus_seconds(10);
+        }
+
+        let redemption_rates = |resp: &HistoricalRedemptionRatesResponse| {
+            resp.redemption_rates
+                .iter()
+                .map(|v| v.redemption_rate.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Newest first by default
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: Some(2),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(redemption_rates(&resp), vec!["1.5", "1.4"]);
+        assert_eq!(resp.next_key, Some(first_height + 3));
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: resp.next_key,
+            limit: Some(2),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(redemption_rates(&resp), vec!["1.3", "1.2"]);
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: resp.next_key,
+            limit: Some(2),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(redemption_rates(&resp), vec!["1.1"]);
+        assert_eq!(resp.next_key, None);
+
+        // Oldest first
+        let msg = QueryMsg::HistoricalPurchaseRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: Some(first_height + 1),
+            limit: Some(2),
+            order: Some(OrderBy::Ascending),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp.purchase_rates
+                .iter()
+                .map(|v| v.update_time)
+                .collect::<Vec<_>>(),
+            vec![1571797439, 1571797449]
+        );
+        assert_eq!(resp.next_key, Some(first_height + 3));
+
+        // The limit is capped
+        let msg = QueryMsg::HistoricalPurchaseRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: Some(u64::MAX),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rates.len(), 5);
+        assert_eq!(resp.next_key, None);
+
+        // A zero limit still returns a page to follow
+        let msg = QueryMsg::HistoricalPurchaseRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: Some(0),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rates.len(), 1);
+        assert_eq!(resp.next_key, Some(first_height + 4));
+    }
+
+    #[test]
+    fn test_rates_at() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+        let first_height = env.block.height;
+
+        let msg = QueryMsg::RedemptionRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Height(first_height),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::NotFound {
+                denom: denom.to_string(),
+                rate: "redemption rate".to_string()
+            }
+        );
+
+        // Rates posted at heights first_height, +10 and +20, 50 seconds apart
+        for rate in ["1.1", "1.2", "1.3"] {
+            let msg = post_rates_msg(denom, "0.9", rate);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            env.block.height += 10;
+            env.block.time = env.block.time.plus_seconds(50);
+        }
+
+        let redemption_rate_at = |at: PointInTime| {
+            let msg = QueryMsg::RedemptionRateAt {
+                denom: denom.to_string(),
+                at,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let resp: RedemptionRate = from_json(&resp).unwrap();
+            resp.redemption_rate.to_string()
+        };
+        assert_eq!(redemption_rate_at(PointInTime::Height(first_height)), "1.1");
+        assert_eq!(
+            redemption_rate_at(PointInTime::Height(first_height + 15)),
+            "1.2"
+        );
+        assert_eq!(
+            redemption_rate_at(PointInTime::Height(first_height + 20)),
+            "1.3"
+        );
+        assert_eq!(redemption_rate_at(PointInTime::Height(u64::MAX)), "1.3");
+        assert_eq!(redemption_rate_at(PointInTime::Time(1571797419)), "1.1");
+        assert_eq!(redemption_rate_at(PointInTime::Time(1571797468)), "1.1");
+        assert_eq!(redemption_rate_at(PointInTime::Time(1571797469)), "1.2");
+
+        let msg = QueryMsg::PurchaseRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Time(1571797500),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRate = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            PurchaseRate {
+                denom: denom.to_string(),
+                purchase_rate: Decimal::from_str("0.9").unwrap(),
+                update_time: 1571797469,
+                block_height: first_height + 10,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+            }
+        );
+
+        // Points before the oldest retained rates
+        let msg = QueryMsg::PurchaseRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Height(first_height - 1),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::PredatesHistory {
+                denom: denom.to_string()
+            }
+        );
+
+        let msg = QueryMsg::RedemptionRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Time(1571797418),
+        };
+        let resp = query(deps.as_ref(), env, msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::PredatesHistory {
+                denom: denom.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_rates_twap() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = QueryMsg::RedemptionRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 100,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::NotFound {
+                denom: denom.to_string(),
+                rate: "redemption rate".to_string()
+            }
+        );
+
+        let msg = post_rates_msg(denom, "0.5", "1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A single rate posted in the current block
+        let msg = QueryMsg::RedemptionRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 100,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateTwapResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            RedemptionRateTwapResponse {
+                redemption_rate: Decimal::one(),
+                num_samples: 1,
+                span_seconds: 0,
+                is_frozen: false,
+            }
+        );
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(40);
+        let msg = post_rates_msg(denom, "0.7", "2");
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(60);
+
+        let redemption_twap = |window_seconds: u64| {
+            let msg = QueryMsg::RedemptionRateTwap {
+                denom: denom.to_string(),
+                window_seconds,
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let resp: RedemptionRateTwapResponse = from_json(&resp).unwrap();
+            resp
+        };
+
+        // 1 during 40 seconds, then 2 during 60 seconds
+        assert_eq!(
+            redemption_twap(100),
+            RedemptionRateTwapResponse {
+                redemption_rate: Decimal::from_str("1.6").unwrap(),
+                num_samples: 2,
+                span_seconds: 100,
+                is_frozen: false,
+            }
+        );
+        // Only the latest rate is in effect within the window
+        assert_eq!(
+            redemption_twap(50),
+            RedemptionRateTwapResponse {
+                redemption_rate: Decimal::from_str("2").unwrap(),
+                num_samples: 1,
+                span_seconds: 50,
+                is_frozen: false,
+            }
+        );
+        // The history covers only part of the window
+        assert_eq!(
+            redemption_twap(200),
+            RedemptionRateTwapResponse {
+                redemption_rate: Decimal::from_str("1.6").unwrap(),
+                num_samples: 2,
+                span_seconds: 100,
+                is_frozen: false,
+            }
+        );
+
+        let msg = QueryMsg::PurchaseRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 80,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateTwapResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            PurchaseRateTwapResponse {
+                purchase_rate: Decimal::from_str("0.65").unwrap(),
+                num_samples: 2,
+                span_seconds: 80,
+                is_frozen: false,
+            }
+        );
+
+        let msg = QueryMsg::PurchaseRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 0,
+        };
+        let resp = query(deps.as_ref(), env, msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::InvalidParams {
+                reason: "window_seconds must be greater than zero".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_retention() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+        let first_height = env.block.height;
+
+        let history = |deps: &OwnedDeps<MockStorage, MockApi, MockQuerier, Empty>, env: &Env| {
+            let msg = QueryMsg::HistoricalPurchaseRates {
+                denom: denom.to_string(),
+                params: None,
+                start_after: None,
+                limit: Some(100),
+                order: Some(OrderBy::Ascending),
+            };
+            let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+            resp.purchase_rates
+                .iter()
+                .map(|v| v.update_time)
+                .collect::<Vec<_>>()
+        };
+
+        let msg = QueryMsg::Retention {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Retention = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            Retention {
+                max_count: Some(100),
+                max_age_seconds: None,
+            }
+        );
+
+        // The default retention keeps the latest 100 rates
+        for _ in 0..101 {
+            let msg = post_rates_msg(denom, "0.9", "1.1");
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            env.block.height += 1;
+            env.block.time = env.block.time.plus_seconds(10);
+        }
+        let rates = history(&deps, &env);
+        assert_eq!(rates.len(), 100);
+        assert_eq!(rates[0], 1571797429);
+
+        // Lowering the limit prunes all the excess rates at once
+        let msg = ExecuteMsg::SetRetention {
+            denom: denom.to_string(),
+            retention: Some(Retention {
+                max_count: Some(3),
+                max_age_seconds: None,
+            }),
+        };
+        let resp = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![attr("action", "set_retention"), attr("denom", denom)]
+        );
+        assert_eq!(
+            history(&deps, &env),
+            vec![1571798399, 1571798409, 1571798419]
+        );
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            history(&deps, &env),
+            vec![1571798409, 1571798419, 1571798429]
+        );
+
+        // Rates older than the maximum age are pruned, except the latest ones
+        let msg = ExecuteMsg::SetRetention {
+            denom: denom.to_string(),
+            retention: Some(Retention {
+                max_count: None,
+                max_age_seconds: Some(15),
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        assert_eq!(history(&deps, &env), vec![1571798419, 1571798429]);
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(1000);
+        let msg = ExecuteMsg::SetRetention {
+            denom: denom.to_string(),
+            retention: Some(Retention {
+                max_count: None,
+                max_age_seconds: Some(10),
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+        assert_eq!(history(&deps, &env), vec![1571798429]);
+
+        let msg = QueryMsg::PurchaseRateAt {
+            denom: denom.to_string(),
+            at: PointInTime::Height(first_height),
+        };
+        let resp = query(deps.as_ref(), env, msg);
+        assert_eq!(
+            resp.unwrap_err(),
+            QueryError::PredatesHistory {
+                denom: denom.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_retention_invalid() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+
+        let msg = ExecuteMsg::SetRetention {
+            denom: "factory/denom".to_string(),
+            retention: Some(Retention {
+                max_count: None,
+                max_age_seconds: None,
+            }),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidRetention {
+                reason: "max_count or max_age_seconds must be set".to_string()
+            }
+        );
+
+        let msg = ExecuteMsg::SetRetention {
+            denom: "factory/denom".to_string(),
+            retention: Some(Retention {
+                max_count: Some(0),
+                max_age_seconds: None,
+            }),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin_info, msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidRetention {
+                reason: "max_count must be greater than zero".to_string()
+            }
+        );
+
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    /// Storage counting the entries read and written, as a proxy for gas
+    #[derive(Default)]
+    struct CountingStorage {
+        inner: MockStorage,
+        reads: Cell<u64>,
+        writes: u64,
+    }
+
+    impl CountingStorage {
+        fn operations(&self) -> u64 {
+            self.reads.get() + self.writes
+        }
+    }
+
+    impl Storage for CountingStorage {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.reads.set(self.reads.get() + 1);
+            self.inner.get(key)
+        }
+
+        fn range<'a>(
+            &'a self,
+            start: Option<&[u8]>,
+            end: Option<&[u8]>,
+            order: Order,
+        ) -> Box<dyn Iterator<Item = Record> + 'a> {
+            Box::new(
+                self.inner
+                    .range(start, end, order)
+                    .inspect(|_| self.reads.set(self.reads.get() + 1)),
+            )
+        }
+
+        fn set(&mut self, key: &[u8], value: &[u8]) {
+            self.writes += 1;
+            self.inner.set(key, value)
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.writes += 1;
+            self.inner.remove(key)
+        }
+    }
+
+    #[test]
+    fn test_post_rates_constant_cost() {
+        let mut deps = OwnedDeps {
+            storage: CountingStorage::default(),
+            api: MockApi::default(),
+            querier: MockQuerier::default(),
+            custom_query_type: PhantomData::<Empty>,
+        };
+        let mut env = mock_env();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let info = mock_info(FEEDER_ADDRESS, &[]);
+        let denom = "factory/denom";
+
+        let msg = InstantiateMsg {
+            admin_address: ADMIN_ADDRESS.to_string(),
+            max_rate: None,
+        };
+        instantiate(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        let msgs = vec![
+            ExecuteMsg::AddFeeder {
+                address: FEEDER_ADDRESS.to_string(),
+            },
+            ExecuteMsg::RegisterDenom {
+                denom: denom.to_string(),
+                metadata: denom_metadata(),
+            },
+            ExecuteMsg::SetRetention {
+                denom: denom.to_string(),
+                retention: Some(Retention {
+                    max_count: Some(1000),
+                    max_age_seconds: None,
+                }),
+            },
+        ];
+        for msg in msgs {
+            execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        }
+
+        let mut post_rates = |deps: &mut OwnedDeps<CountingStorage, MockApi, MockQuerier>| {
+            env.block.height += 1;
+            env.block.time = env.block.time.plus_seconds(10);
+            let before = deps.storage.operations();
+            let msg = post_rates_msg(denom, "0.9", "1.1");
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            deps.storage.operations() - before
+        };
+
+        for _ in 0..10 {
+            post_rates(&mut deps);
+        }
+        let cost_with_short_history = post_rates(&mut deps);
+
+        for _ in 0..500 {
+            post_rates(&mut deps);
+        }
+        let cost_with_long_history = post_rates(&mut deps);
+        assert_eq!(cost_with_short_history, cost_with_long_history);
+
+        // Pruning at the retention limit costs a constant number of extra operations
+        let msg = ExecuteMsg::SetRetention {
+            denom: denom.to_string(),
+            retention: Some(Retention {
+                max_count: Some(100),
+                max_age_seconds: None,
+            }),
+        };
+        execute(deps.as_mut(), mock_env(), admin_info, msg).unwrap();
+        let cost_with_pruning = post_rates(&mut deps);
+        for _ in 0..100 {
+            post_rates(&mut deps);
+        }
+        assert_eq!(post_rates(&mut deps), cost_with_pruning);
+        assert!(cost_with_pruning - cost_with_long_history <= 3);
+    }
+
+    #[test]
+    fn test_post_single_rate() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        // Neither rate set
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: None,
+            redemption_rate: None,
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::NoRates {});
+
+        // The first rates of a denom must include both
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: None,
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MissingRate {
+                field: "purchase_rate".to_string(),
+                denom: denom.to_string(),
+            }
+        );
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let first_time = env.block.time.seconds();
+
+        // Only the redemption rate, the purchase rate is kept
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: None,
+            redemption_rate: Some("1.2".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.attributes.iter().all(|a| a.key != "purchase_rate"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "redemption_rate" && a.value == "1.2"));
+
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rate, Decimal::from_str("0.9").unwrap());
+        assert_eq!(resp.update_time, first_time);
+        assert_eq!(resp.block_height, env.block.height - 1);
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.2").unwrap());
+        assert_eq!(resp.update_time, env.block.time.seconds());
+        assert_eq!(resp.block_height, env.block.height);
+
+        // Only the purchase rate, twice
+        for purchase_rate in ["0.8", "0.7"] {
+            env.block.height += 1;
+            env.block.time = env.block.time.plus_seconds(10);
+            let msg = ExecuteMsg::PostRates {
+                denom: denom.to_string(),
+                purchase_rate: Some(purchase_rate.to_string()),
+                redemption_rate: None,
+                slashing: None,
+                source_update_time: None,
+                source_block_height: None,
+            };
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.2").unwrap());
+        assert_eq!(resp.update_time, first_time + 10);
+
+        // The history only lists the updates in which each rate was posted
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: Some(1),
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rates.len(), 1);
+        assert_eq!(
+            resp.redemption_rates[0].redemption_rate,
+            Decimal::from_str("1.2").unwrap()
+        );
+        assert_eq!(resp.redemption_rates[0].update_time, first_time + 10);
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: resp.next_key,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rates.len(), 1);
+        assert_eq!(resp.redemption_rates[0].update_time, first_time);
+        assert_eq!(resp.next_key, None);
+
+        let msg = QueryMsg::HistoricalPurchaseRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp.purchase_rates
+                .iter()
+                .map(|v| v.purchase_rate.to_string())
+                .collect::<Vec<_>>(),
+            vec!["0.7", "0.8", "0.9"]
+        );
+
+        // The averages only count the updates in which each rate was posted
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = QueryMsg::RedemptionRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 40,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateTwapResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.175").unwrap());
+        assert_eq!(resp.num_samples, 2);
+        assert_eq!(resp.span_seconds, 40);
+
+        let msg = QueryMsg::PurchaseRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 40,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: PurchaseRateTwapResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rate, Decimal::from_str("0.825").unwrap());
+        assert_eq!(resp.num_samples, 3);
+        assert_eq!(resp.span_seconds, 40);
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let (mut deps, mut env, _info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+        let feeders = ["feeder_1", "feeder_2", "feeder_3"];
+        for feeder in feeders {
+            let msg = ExecuteMsg::AddFeeder {
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        }
+
+        // Only the admin can set the aggregation
+        let aggregation = Aggregation {
+            quorum: 3,
+            window_seconds: 60,
+        };
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(aggregation.clone()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(FEEDER_ADDRESS, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::Aggregation {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<Aggregation> = from_json(&resp).unwrap();
+        assert_eq!(resp, Some(aggregation));
+
+        // Submissions need both rates
+        let msg = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: None,
+            redemption_rate: Some("1.1".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(feeders[0], &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IncompleteSubmission {
+                denom: denom.to_string()
+            }
+        );
+
+        let msg = post_rates_msg(denom, "0.9", "1.3");
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(feeders[0], &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "submit_rates"),
+                attr("denom", denom),
+                attr("round", "1"),
+                attr("submissions", "1"),
+            ]
+        );
+
+        // A feeder submits once per round
+        let err = execute(deps.as_mut(), env.clone(), mock_info(feeders[0], &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::AlreadySubmitted {
+                denom: denom.to_string(),
+                round: 1,
+            }
+        );
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg(denom, "0.8", "1.1");
+        execute(deps.as_mut(), env.clone(), mock_info(feeders[1], &[]), msg).unwrap();
+
+        // Nothing is stored before the quorum is reached
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        query(deps.as_ref(), env.clone(), msg).unwrap_err();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg(denom, "1", "1.2");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(feeders[2], &[]), msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "post_rates"));
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.2").unwrap());
+        assert_eq!(resp.feeder, env.contract.address);
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rate, Decimal::from_str("0.9").unwrap());
+
+        let msg = QueryMsg::AggregationRound {
+            denom: denom.to_string(),
+            round: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<AggregationRound> = from_json(&resp).unwrap();
+        let round = resp.unwrap();
+        assert_eq!(round.id, 1);
+        assert!(round.finalized);
+        assert_eq!(
+            round.submissions[1],
+            Submission {
+                feeder: Addr::unchecked(feeders[1]),
+                source_update_time: None,
+                source_block_height: None,
+                purchase_rate: Decimal::from_str("0.8").unwrap(),
+                redemption_rate: Decimal::from_str("1.1").unwrap(),
+                update_time: 1571797429,
+            }
+        );
+
+        // A round whose window elapses before the quorum is dropped
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg(denom, "0.9", "1.4");
+        execute(deps.as_mut(), env.clone(), mock_info(feeders[0], &[]), msg).unwrap();
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(61);
+        let msg = post_rates_msg(denom, "0.9", "1.3");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(feeders[1], &[]), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("round", "3"));
+        assert_eq!(res.attributes[3], attr("submissions", "1"));
+
+        // Earlier rounds are kept with their submissions
+        let msg = QueryMsg::AggregationRound {
+            denom: denom.to_string(),
+            round: Some(1),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<AggregationRound> = from_json(&resp).unwrap();
+        let round = resp.unwrap();
+        assert_eq!(round.id, 1);
+        assert_eq!(round.submissions.len(), 3);
+        let msg = QueryMsg::AggregationRound {
+            denom: denom.to_string(),
+            round: Some(2),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<AggregationRound> = from_json(&resp).unwrap();
+        assert!(!resp.unwrap().finalized);
+
+        // The quorum must stay reachable by the registered feeders
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(Aggregation {
+                quorum: 5,
+                window_seconds: 60,
+            }),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAggregation {
+                reason: "quorum exceeds the 4 registered feeders".to_string()
+            }
+        );
+        let msg = ExecuteMsg::RemoveFeeder {
+            address: FEEDER_ADDRESS.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::RemoveFeeder {
+            address: feeders[0].to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAggregation {
+                reason: format!("quorum of {denom} exceeds the 2 remaining feeders")
+            }
+        );
+
+        // Even number of submissions
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(Aggregation {
+                quorum: 2,
+                window_seconds: 60,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        let msg = post_rates_msg(denom, "0.9", "1.4");
+        execute(deps.as_mut(), env.clone(), mock_info(feeders[2], &[]), msg).unwrap();
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.35").unwrap());
+
+        // Invalid aggregations
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(Aggregation {
+                quorum: 0,
+                window_seconds: 60,
+            }),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAggregation {
+                reason: "quorum must be greater than zero".to_string()
+            }
+        );
+
+        // Without aggregation, rates are stored directly again
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: None,
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+        let msg = QueryMsg::AggregationRound {
+            denom: denom.to_string(),
+            round: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Option<AggregationRound> = from_json(&resp).unwrap();
+        assert_eq!(resp, None);
+
+        env.block.height += 1;
+        let msg = post_rates_msg(denom, "0.9", "1.5");
+        let res = execute(deps.as_mut(), env, mock_info(feeders[0], &[]), msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "post_rates"));
+    }
+
+    #[test]
+    fn test_pause() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let guardian_info = mock_info("guardian", &[]);
+        let denom = "factory/denom";
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Only the admin can set the guardian
+        let msg = ExecuteMsg::SetGuardian {
+            address: Some("guardian".to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            guardian_info.clone(),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let resp = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let resp: Config = from_json(&resp).unwrap();
+        assert_eq!(resp.guardian, Some(Addr::unchecked("guardian")));
+
+        // Feeders can't pause
+        let msg = ExecuteMsg::Pause {
+            denom: Some(denom.to_string()),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), env.clone(), guardian_info.clone(), msg).unwrap();
+
+        env.block.height += 1;
+        let msg = post_rates_msg(denom, "0.9", "1.2");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DenomPaused {
+                denom: denom.to_string()
+            }
+        );
+
+        // The latest rates are still returned, flagged as frozen
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.1").unwrap());
+        assert!(resp.is_frozen);
+
+        let msg = QueryMsg::Paused { denom: None };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: bool = from_json(&resp).unwrap();
+        assert!(!resp);
+
+        // The guardian can't unpause
+        let msg = ExecuteMsg::Unpause {
+            denom: Some(denom.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            guardian_info.clone(),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = post_rates_msg(denom, "0.9", "1.2");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Global pause
+        let msg = ExecuteMsg::Pause { denom: None };
+        execute(deps.as_mut(), env.clone(), guardian_info, msg).unwrap();
+
+        env.block.height += 1;
+        let msg = post_rates_msg(denom, "0.9", "1.3");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+
+        let msg = QueryMsg::PurchaseRateTwap {
+            denom: denom.to_string(),
+            window_seconds: 60,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: PurchaseRateTwapResponse = from_json(&resp).unwrap();
+        assert!(resp.is_frozen);
+
+        let msg = QueryMsg::Paused {
+            denom: Some(denom.to_string()),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: bool = from_json(&resp).unwrap();
+        assert!(resp);
+
+        let msg = ExecuteMsg::Unpause { denom: None };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+
+        let msg = post_rates_msg(denom, "0.9", "1.3");
+        execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_rates() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A second update in the same block is rejected, the first one stays
+        let msg = post_rates_msg(denom, "0.9", "1.2");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DuplicateRates {
+                denom: denom.to_string(),
+                block_height: env.block.height,
+            }
+        );
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.1").unwrap());
+
+        // Also within a batch
+        env.block.height += 1;
+        let update = RateUpdate {
+            denom: denom.to_string(),
+            purchase_rate: Some("0.9".to_string()),
+            redemption_rate: Some("1.2".to_string()),
+            slashing: None,
+            source_update_time: None,
+            source_block_height: None,
+        };
+        let msg = ExecuteMsg::PostRatesBatch {
+            rates: vec![update.clone(), update],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::DuplicateRates {
+                denom: denom.to_string(),
+                block_height: env.block.height,
+            }
+        );
+    }
+
+    #[test]
+    fn test_source_observation() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+        let now = env.block.time.seconds();
+        let post_rates_msg = |redemption_rate: &str, source_update_time, source_block_height| {
+            ExecuteMsg::PostRates {
+                denom: denom.to_string(),
+                purchase_rate: Some("0.9".to_string()),
+                redemption_rate: Some(redemption_rate.to_string()),
+                slashing: None,
+                source_update_time,
+                source_block_height,
+            }
+        };
+
+        // Observations can't be in the future
+        let msg = post_rates_msg("1.1", Some(now + 1), Some(100));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceTimeInFuture {
+                source_update_time: now + 1
+            }
+        );
+
+        let msg = post_rates_msg("1.1", Some(now - 30), Some(100));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("source_update_time", (now - 30).to_string())));
+        assert!(res.attributes.contains(&attr("source_block_height", "100")));
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.update_time, now);
+        assert_eq!(resp.source_update_time, Some(now - 30));
+        assert_eq!(resp.source_block_height, Some(100));
+
+        // Observations must be strictly newer than the previous one
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg("1.2", Some(now - 30), Some(101));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceNotNewer {
+                field: "source_update_time".to_string(),
+                value: now - 30,
+                previous: now - 30,
+            }
+        );
+
+        let msg = post_rates_msg("1.2", Some(now - 20), Some(99));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceNotNewer {
+                field: "source_block_height".to_string(),
+                value: 99,
+                previous: 100,
+            }
+        );
+
+        let msg = post_rates_msg("1.2", Some(now - 20), Some(101));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp.redemption_rates
+                .iter()
+                .map(|v| v.source_block_height)
+                .collect::<Vec<_>>(),
+            vec![Some(101), Some(100)]
+        );
+
+        // Rates posted without an observation don't reset the previous one
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg("1.3", None, None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg("1.4", Some(now - 25), None);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceNotNewer {
+                field: "source_update_time".to_string(),
+                value: now - 25,
+                previous: now - 20,
+            }
+        );
+        let msg = post_rates_msg("1.4", None, Some(101));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceNotNewer {
+                field: "source_block_height".to_string(),
+                value: 101,
+                previous: 101,
+            }
+        );
+
+        let msg = post_rates_msg("1.4", Some(now - 10), Some(102));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Finalized rounds record the newest observation of their submissions
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let msg = ExecuteMsg::AddFeeder {
+            address: "feeder_2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(Aggregation {
+                quorum: 2,
+                window_seconds: 60,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg("1.5", Some(now - 5), Some(105));
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = post_rates_msg("1.5", Some(now - 8), Some(103));
+        execute(deps.as_mut(), env.clone(), mock_info("feeder_2", &[]), msg).unwrap();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = post_rates_msg("1.6", Some(now), Some(104));
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::SourceNotNewer {
+                field: "source_block_height".to_string(),
+                value: 104,
+                previous: 105,
+            }
+        );
+    }
+
+    #[test]
+    fn test_price() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+        let underlying_update_time = env.block.time.seconds() - 100;
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "price_source" => {
+                let PriceSourceQueryMsg::Price { denom } = from_json(msg).unwrap();
+                assert_eq!(denom, "utia");
+                let resp = PriceSourceResponse {
+                    price: Decimal::from_str("5").unwrap(),
+                    update_time: underlying_update_time,
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&resp).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::Price {
+            denom: denom.to_string(),
+        };
+        let err = query(deps.as_ref(), env.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err, QueryError::PriceSourceNotConfigured {});
+        // Contracts querying this one only get the message, led by its code
+        assert_eq!(
+            err.to_string(),
+            "price_source_not_configured: Price source not configured"
+        );
+
+        let set_price_source = |max_price_age_seconds| ExecuteMsg::SetPriceSource {
+            price_source: Some(PriceSourceInfo {
+                address: "price_source".to_string(),
+                max_price_age_seconds,
+            }),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(FEEDER_ADDRESS, &[]),
+            set_price_source(60),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            set_price_source(0),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::InvalidPriceSource {});
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            set_price_source(60),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_price_source"),
+                attr("price_source", "price_source"),
+                attr("max_price_age_seconds", "60"),
+            ]
+        );
+
+        // The redemption rate must be bounded in age
+        let err = query(deps.as_ref(), env.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::MaxStalenessNotConfigured {
+                denom: denom.to_string()
+            }
+        );
+        let set_max_staleness = ExecuteMsg::SetMaxStaleness {
+            denom: denom.to_string(),
+            max_staleness_seconds: Some(150),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            set_max_staleness,
+        )
+        .unwrap();
+
+        // The underlying price is held to the price source's maximum age
+        let err = query(deps.as_ref(), env.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Stale {
+                denom: denom.to_string(),
+                rate: "underlying price".to_string()
+            }
+        );
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin_info.clone(),
+            set_price_source(100),
+        )
+        .unwrap();
+
+        let resp = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let resp: PriceResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            PriceResponse {
+                price: Decimal::from_str("5.5").unwrap(),
+                redemption_rate: Decimal::from_str("1.1").unwrap(),
+                redemption_rate_update_time: env.block.time.seconds(),
+                underlying_price: Decimal::from_str("5").unwrap(),
+                underlying_price_update_time: underlying_update_time,
+            }
+        );
+
+        // Rates of a paused denom are not priced
+        let pause_msg = ExecuteMsg::Pause {
+            denom: Some(denom.to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), pause_msg).unwrap();
+        let err = query(deps.as_ref(), env.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Paused {
+                denom: denom.to_string()
+            }
+        );
+        let unpause_msg = ExecuteMsg::Unpause {
+            denom: Some(denom.to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, unpause_msg).unwrap();
+        query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(151);
+        let err = query(deps.as_ref(), env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Stale {
+                denom: denom.to_string(),
+                rate: "redemption rate".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_params() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+        let first_height = env.block.height;
+
+        for rate in ["1.1", "1.2"] {
+            let msg = post_rates_msg(denom, "0.9", rate);
+            execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            env.block.height += 10;
+            env.block.time = env.block.time.plus_seconds(100);
+        }
+        let params =
+            |params: RateQueryParamsV1| Some(to_json_binary(&RateQueryParams::V1(params)).unwrap());
+
+        // Without a maximum staleness the rates are fresh by default
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1::default()),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert!(!resp.is_stale);
+
+        // Older than the maximum age, flagged by default
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(99),
+                ..Default::default()
+            }),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.2").unwrap());
+        assert!(resp.is_stale);
+
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(99),
+                min_block_height: None,
+                on_stale: Some(OnStale::Error),
+            }),
+        };
+        let err = query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Stale {
+                denom: denom.to_string(),
+                rate: "purchase rate".to_string()
+            }
+        );
+
+        // Posted below the minimum block height
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(100),
+                min_block_height: Some(first_height + 11),
+                on_stale: Some(OnStale::Error),
+            }),
+        };
+        let err = query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Stale {
+                denom: denom.to_string(),
+                rate: "redemption rate".to_string()
+            }
+        );
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(100),
+                min_block_height: Some(first_height + 10),
+                on_stale: Some(OnStale::Error),
+            }),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert!(!resp.is_stale);
+
+        // The history is bounded by the options
+        let msg = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                min_block_height: Some(first_height + 1),
+                ..Default::default()
+            }),
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rates.len(), 1);
+        assert_eq!(resp.redemption_rates[0].block_height, first_height + 10);
+
+        let msg = QueryMsg::HistoricalPurchaseRates {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(200),
+                ..Default::default()
+            }),
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: HistoricalPurchaseRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.purchase_rates.len(), 2);
+
+        // The maximum age can't loosen the denom's maximum staleness
+        let msg = ExecuteMsg::SetMaxStaleness {
+            denom: denom.to_string(),
+            max_staleness_seconds: Some(50),
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADMIN_ADDRESS, &[]),
+            msg,
+        )
+        .unwrap();
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: params(RateQueryParamsV1 {
+                max_age_seconds: Some(100),
+                ..Default::default()
+            }),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert!(resp.is_stale);
+
+        // The options are passed as JSON
+        let msg = QueryMsg::PurchaseRate {
+            denom: denom.to_string(),
+            params: Some(Binary::from(
+                br#"{"v1":{"max_age_seconds":99,"on_stale":"error"}}"#.as_slice(),
+            )),
+        };
+        let err = query(deps.as_ref(), env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::Stale {
+                denom: denom.to_string(),
+                rate: "purchase rate".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_latest_rates_of_many_denoms() {
+        let (mut deps, env, info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        for denom in ["factory/a", "factory/b"] {
+            let msg = ExecuteMsg::RegisterDenom {
+                denom: denom.to_string(),
+                metadata: denom_metadata(),
+            };
+            execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        }
+        let msg = post_rates_msg("factory/a", "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = post_rates_msg("factory/denom", "0.8", "1.2");
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::RedemptionRates {
+            denoms: vec!["factory/denom".to_string(), "factory/unknown".to_string()],
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: LatestRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.rates.len(), 2);
+        assert_eq!(resp.rates[0].denom, "factory/denom");
+        assert_eq!(
+            resp.rates[0]
+                .redemption_rate
+                .as_ref()
+                .unwrap()
+                .redemption_rate,
+            Decimal::from_str("1.2").unwrap()
+        );
+        assert_eq!(
+            resp.rates[0].purchase_rate.as_ref().unwrap().purchase_rate,
+            Decimal::from_str("0.8").unwrap()
+        );
+        assert_eq!(
+            resp.rates[1],
+            LatestRates {
+                denom: "factory/unknown".to_string(),
+                purchase_rate: None,
+                redemption_rate: None,
+            }
+        );
+
+        let msg = QueryMsg::RedemptionRates {
+            denoms: vec!["factory/denom".to_string(); 101],
+        };
+        let err = query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::InvalidParams {
+                reason: "at most 100 denoms".to_string()
+            }
+        );
+
+        // Paginated over the registered denoms
+        let msg = QueryMsg::AllLatestRates {
+            start_after: None,
+            limit: Some(2),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: LatestRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(
+            resp.rates
+                .iter()
+                .map(|v| (v.denom.as_str(), v.redemption_rate.is_some()))
+                .collect::<Vec<_>>(),
+            vec![("factory/a", true), ("factory/b", false)]
+        );
+
+        let msg = QueryMsg::AllLatestRates {
+            start_after: Some("factory/b".to_string()),
+            limit: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: LatestRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.rates.len(), 1);
+        assert_eq!(resp.rates[0].denom, "factory/denom");
+    }
+
+    #[test]
+    fn test_rates() {
+        let (mut deps, mut env, info) = default_instantiate_with_feeder();
+        let denom = "factory/denom";
+
+        let msg = QueryMsg::Rates {
+            denom: denom.to_string(),
+        };
+        let err = query(deps.as_ref(), env.clone(), msg.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            QueryError::NotFound {
+                denom: denom.to_string(),
+                rate: "rates".to_string()
+            }
+        );
+
+        let msg_post = post_rates_msg(denom, "0.9", "1.1");
+        execute(deps.as_mut(), env.clone(), info.clone(), msg_post).unwrap();
+        let first_height = env.block.height;
+        let first_time = env.block.time.seconds();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg_post = ExecuteMsg::PostRates {
+            denom: denom.to_string(),
+            purchase_rate: None,
+            redemption_rate: Some("1.2".to_string()),
+            slashing: None,
+            source_update_time: Some(first_time),
+            source_block_height: Some(100),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg_post).unwrap();
+
+        let resp = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let resp: Rates = from_json(&resp).unwrap();
+        assert_eq!(
+            resp,
+            Rates {
+                purchase_rate: Decimal::from_str("0.9").unwrap(),
+                redemption_rate: Decimal::from_str("1.2").unwrap(),
+                update_time: env.block.time.seconds(),
+                block_height: env.block.height,
+                feeder: Addr::unchecked(FEEDER_ADDRESS),
+                source_update_time: Some(first_time),
+                source_block_height: Some(100),
+                slashing: false,
+                purchase_rate_carried_from: Some(RateOrigin {
+                    update_time: first_time,
+                    block_height: first_height,
+                    feeder: Addr::unchecked(FEEDER_ADDRESS),
+                    source_update_time: None,
+                    source_block_height: None,
+                    slashing: false,
+                }),
+                redemption_rate_carried_from: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_address() {
+        let (mut deps, env, info) = default_instantiate();
+
+        let msg = ExecuteMsg::AddFeeder {
+            address: "".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAddress {
+                address: "".to_string()
+            }
+        );
+
+        let msg = ExecuteMsg::SetGuardian {
+            address: Some("".to_string()),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidAddress {
+                address: "".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_migrate_from_v1_0_0() {
+        let (mut deps, env) = v1_0_0_mock();
+        let denom = "factory/denom";
+        let now = env.block.time.seconds();
+
+        for (denom, block_height, redemption_rate) in [
+            (denom, 10, "1.1"),
+            (denom, 20, "1.2"),
+            ("factory/other", 15, "1.5"),
+        ] {
+            let legacy_rates = v1_0_0::Rates {
+                purchase_rate: Decimal::from_str("0.9").unwrap(),
+                redemption_rate: Decimal::from_str(redemption_rate).unwrap(),
+                update_time: now - 100 + block_height,
+            };
+            v1_0_0::RATES
+                .save(deps.as_mut().storage, (denom, block_height), &legacy_rates)
+                .unwrap();
+        }
+
+        let msg = MigrateMsg {
+            feeders: Some(vec![ADMIN_ADDRESS.to_string()]),
+            denoms: Some(vec![DenomInfo {
+                denom: denom.to_string(),
+                metadata: denom_metadata(),
+            }]),
+        };
+        let resp = migrate(deps.as_mut(), env.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            resp.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("from_version", "1.0.0"),
+                attr("to_version", env!("CARGO_PKG_VERSION")),
+            ]
+        );
+
+        let msg_config = QueryMsg::Config {};
+        let resp = query(deps.as_ref(), env.clone(), msg_config).unwrap();
+        let config: Config = from_json(&resp).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                admin_address: Addr::unchecked(ADMIN_ADDRESS),
+                max_rate: None,
+                guardian: None,
+                price_source: None,
+            }
+        );
+
+        // The rates are attributed to the admin, at the block height they
+        // were stored with
+        let msg_rates = QueryMsg::Rates {
+            denom: denom.to_string(),
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_rates).unwrap();
+        let rates: Rates = from_json(&resp).unwrap();
+        assert_eq!(
+            rates,
+            Rates {
+                purchase_rate: Decimal::from_str("0.9").unwrap(),
+                redemption_rate: Decimal::from_str("1.2").unwrap(),
+                update_time: now - 80,
+                block_height: 20,
+                feeder: Addr::unchecked(ADMIN_ADDRESS),
+                source_update_time: None,
+                source_block_height: None,
+                slashing: false,
+                purchase_rate_carried_from: None,
+                redemption_rate_carried_from: None,
+            }
+        );
+
+        let msg_history = QueryMsg::HistoricalRedemptionRates {
+            denom: denom.to_string(),
+            params: None,
+            start_after: None,
+            limit: None,
+            order: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_history.clone()).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        let block_heights: Vec<u64> = resp
+            .redemption_rates
+            .iter()
+            .map(|v| v.block_height)
+            .collect();
+        assert_eq!(block_heights, vec![20, 10]);
+
+        let msg_other = QueryMsg::RedemptionRate {
+            denom: "factory/other".to_string(),
+            params: None,
+        };
+        let resp = query(deps.as_ref(), env.clone(), msg_other).unwrap();
+        let resp: RedemptionRateResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rate, Decimal::from_str("1.5").unwrap());
+        assert_eq!(resp.block_height, 15);
+
+        // The registered admin keeps posting rates for the registered denom
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let msg_post = post_rates_msg(denom, "0.9", "1.3");
+        execute(deps.as_mut(), env.clone(), admin_info, msg_post).unwrap();
+        let resp = query(deps.as_ref(), env.clone(), msg_history).unwrap();
+        let resp: HistoricalRedemptionRatesResponse = from_json(&resp).unwrap();
+        assert_eq!(resp.redemption_rates.len(), 3);
+        assert_eq!(
+            resp.redemption_rates[0].redemption_rate,
+            Decimal::from_str("1.3").unwrap()
+        );
+
+        // Cannot migrate twice to the same version
+        let err = migrate(deps.as_mut(), env, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidContractVersion {});
+    }
+
+    #[test]
+    fn test_migrate_options() {
+        let (mut deps, env) = v1_0_0_mock();
+        let msg = MigrateMsg {
+            feeders: Some(vec![ADMIN_ADDRESS.to_string(), ADMIN_ADDRESS.to_string()]),
+            denoms: None,
+        };
+        let err = migrate(deps.as_mut(), env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::FeederAlreadyRegistered {
+                address: ADMIN_ADDRESS.to_string()
+            }
+        );
+
+        let (mut deps, env) = v1_0_0_mock();
+        let msg = MigrateMsg {
+            feeders: None,
+            denoms: Some(vec![DenomInfo {
+                denom: "factory/denom".to_string(),
+                metadata: DenomMetadata {
+                    base_denom: "".to_string(),
+                    ..denom_metadata()
+                },
+            }]),
+        };
+        let err = migrate(deps.as_mut(), env, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidDenomMetadata {
+                field: "base_denom".to_string()
+            }
+        );
+
+        let (mut deps, env) = v1_0_0_mock();
+        set_contract_version(deps.as_mut().storage, "crates.io:other", "1.0.0").unwrap();
+        let msg = MigrateMsg {
+            feeders: None,
+            denoms: None,
+        };
+        let err = migrate(deps.as_mut(), env, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidContract {});
+    }
+
+    #[test]
+    fn test_remove_feeder_drops_submissions() {
+        let (mut deps, env, _info) = default_instantiate_with_feeder();
+        let admin_info = mock_info(ADMIN_ADDRESS, &[]);
+        let denom = "factory/denom";
+        let feeders = ["feeder_1", "feeder_2"];
+        for feeder in feeders {
+            let msg = ExecuteMsg::AddFeeder {
+                address: feeder.to_string(),
+            };
+            execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetAggregation {
+            denom: denom.to_string(),
+            aggregation: Some(Aggregation {
+                quorum: 2,
+                window_seconds: 60,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+        let msg = post_rates_msg(denom, "0.9", "1000");
+        execute(deps.as_mut(), env.clone(), mock_info(feeders[0], &[]), msg).unwrap();
+        let msg = ExecuteMsg::RemoveFeeder {
+            address: feeders[0].to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+
+        // The remaining submission alone does not reach the quorum
+        let msg = post_rates_msg(denom, "0.9", "1.1");
+        let res = execute(deps.as_mut(), env.clone(), mock_info(feeders[1], &[]), msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "submit_rates"));
+        assert_eq!(res.attributes[3], attr("submissions", "1"));
+
+        let msg = QueryMsg::RedemptionRate {
+            denom: denom.to_string(),
+            params: None,
+        };
+        query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        let msg = QueryMsg::AggregationRound {
+            denom: denom.to_string(),
+            round: None,
+        };
+        let resp = query(deps.as_ref(), env, msg).unwrap();
+        let resp: Option<AggregationRound> = from_json(&resp).unwrap();
+        let round = resp.unwrap();
+        assert!(!round.finalized);
+        assert_eq!(round.submissions.len(), 1);
+        assert_eq!(round.submissions[0].feeder, Addr::unchecked(feeders[1]));
     }
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Decimal, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -9,9 +9,144 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("Invalid address: {address}")]
+    InvalidAddress { address: String },
+
     #[error("Cannot upgrade to a different contract")]
     InvalidContract {},
 
     #[error("Invalid contract version")]
     InvalidContractVersion {},
+
+    #[error("Rates batch must not be empty")]
+    EmptyBatch {},
+
+    #[error("Feeder {address} is already registered")]
+    FeederAlreadyRegistered { address: String },
+
+    #[error("Feeder {address} is not registered")]
+    FeederNotRegistered { address: String },
+
+    #[error("No pending admin")]
+    NoPendingAdmin {},
+
+    #[error("Admin proposal expired")]
+    AdminProposalExpired {},
+
+    #[error("Invalid expiry: {expiry} is not in the future")]
+    InvalidExpiry { expiry: u64 },
+
+    #[error("At least one of purchase_rate and redemption_rate must be set")]
+    NoRates {},
+
+    #[error("Slashing events of {denom} must set redemption_rate")]
+    SlashingWithoutRedemptionRate { denom: String },
+
+    #[error("{field} must be set for the first rates of {denom}")]
+    MissingRate { field: String, denom: String },
+
+    #[error("Invalid {field}: {value}")]
+    InvalidRate { field: String, value: String },
+
+    #[error("Invalid {field}: must be greater than zero")]
+    ZeroRate { field: String },
+
+    #[error("Invalid {field}: {value} is above the maximum rate {max_rate}")]
+    RateAboveCeiling {
+        field: String,
+        value: Decimal,
+        max_rate: Decimal,
+    },
+
+    #[error("Denom {denom} is already registered")]
+    DenomAlreadyRegistered { denom: String },
+
+    #[error("Denom {denom} is not registered")]
+    DenomNotRegistered { denom: String },
+
+    #[error("Invalid denom metadata: {field} must not be empty")]
+    InvalidDenomMetadata { field: String },
+
+    #[error("Invalid retention: {reason}")]
+    InvalidRetention { reason: String },
+
+    #[error("Invalid deviation limits: window_seconds must be greater than zero")]
+    InvalidDeviationLimits {},
+
+    #[error("Invalid price source: max_price_age_seconds must be greater than zero")]
+    InvalidPriceSource {},
+
+    #[error("Invalid source_update_time: {source_update_time} is in the future")]
+    SourceTimeInFuture { source_update_time: u64 },
+
+    #[error("Invalid {field}: {value} is not newer than the previous {previous}")]
+    SourceNotNewer {
+        field: String,
+        value: u64,
+        previous: u64,
+    },
+
+    #[error("Rates for {denom} were already posted at block {block_height}")]
+    DuplicateRates { denom: String, block_height: u64 },
+
+    #[error("Redemption rate of {denom} decreased from {previous} to {current} without a slashing event")]
+    RedemptionRateDecreased {
+        denom: String,
+        previous: Decimal,
+        current: Decimal,
+    },
+
+    #[error("No quarantined rates for {denom}")]
+    NoQuarantinedRates { denom: String },
+
+    #[error("Posting rates is paused")]
+    Paused {},
+
+    #[error("Posting rates for {denom} is paused")]
+    DenomPaused { denom: String },
+
+    #[error("Invalid aggregation: {reason}")]
+    InvalidAggregation { reason: String },
+
+    #[error("Submissions for {denom} must set both purchase_rate and redemption_rate")]
+    IncompleteSubmission { denom: String },
+
+    #[error("Rates for {denom} already submitted in round {round}")]
+    AlreadySubmitted { denom: String, round: u64 },
+}
+
+/// Errors of the queries. Their messages start with a stable code, which
+/// contracts querying this one can match on, followed by `: ` and a
+/// description
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("std_error: {0}")]
+    Std(#[from] StdError),
+
+    #[error("invalid_params: {reason}")]
+    InvalidParams { reason: String },
+
+    #[error("not_found: No {rate} found for {denom}")]
+    NotFound { denom: String, rate: String },
+
+    #[error("predates_history: Requested point predates the retained history of {denom}")]
+    PredatesHistory { denom: String },
+
+    #[error("stale: The {rate} of {denom} is stale")]
+    Stale { denom: String, rate: String },
+
+    #[error("paused: Rates of {denom} are paused")]
+    Paused { denom: String },
+
+    #[error("denom_not_registered: Denom {denom} is not registered")]
+    DenomNotRegistered { denom: String },
+
+    #[error("price_source_not_configured: Price source not configured")]
+    PriceSourceNotConfigured {},
+
+    #[error("max_staleness_not_configured: No maximum staleness configured for {denom}")]
+    MaxStalenessNotConfigured { denom: String },
+
+    #[error("overflow: Overflow computing the {value}")]
+    Overflow { value: String },
 }
@@ -1,53 +1,1114 @@
 use crate::error::ContractError;
-use crate::state::{Rates, CONFIG, MAX_NUM_HISTORICAL_RATES, RATES};
-use cosmwasm_std::{ensure, Decimal, DepsMut, Env, MessageInfo, Order, Response};
-use std::str::FromStr;
+use crate::msg::{PriceSourceInfo, RateUpdate};
+use crate::query::{get_latest_rates, get_latest_round};
+use crate::state::{
+    Aggregation, AggregationRound, Config, DenomMetadata, DeviationLimits, PendingAdmin,
+    PriceSource, QuarantinedRates, RatePolicy, Rates, RatesIndex, Retention, Submission,
+    AGGREGATIONS, AGGREGATION_ROUNDS, CONFIG, DENOMS, DEVIATION_LIMITS, FEEDERS, LATEST_SOURCES,
+    MAX_NUM_AGGREGATION_ROUNDS, MAX_STALENESS, PAUSED, PAUSED_DENOMS, PENDING_ADMIN,
+    QUARANTINED_RATES, RATES, RATES_INDEX, RATE_POLICIES, RETENTION,
+};
+use crate::validation::{
+    parse_rate, validate_address, validate_aggregation, validate_denom_metadata, validate_max_rate,
+    validate_retention,
+};
+use cosmwasm_std::{
+    attr, ensure, Addr, Attribute, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order,
+    Response, StdResult, Storage,
+};
+use cw_storage_plus::Bound;
+
+/// Outcome of a rate update
+enum PostedRates {
+    Stored(Rates),
+    /// The rates exceeded the named deviation limit and wait for the admin's confirmation
+    Quarantined(Rates, &'static str),
+    /// The rates were submitted to an aggregation round which has not reached
+    /// its quorum yet
+    Submitted(AggregationRound),
+}
 
 pub fn post_rates(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    denom: String,
-    purchase_rate: String,
-    redemption_rate: String,
+    update: RateUpdate,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
+    let denom = update.denom.clone();
+
+    match update_rates(deps, &env, &info.sender, update)? {
+        PostedRates::Stored(rates) => Ok(Response::new()
+            .add_attribute("action", "post_rates")
+            .add_attributes(rates_attributes(&denom, &rates))),
+        PostedRates::Quarantined(rates, limit) => Ok(Response::new()
+            .add_attribute("action", "quarantine_rates")
+            .add_attribute("denom", &denom)
+            .add_event(quarantined_event(&denom, &rates, &info.sender, limit))),
+        PostedRates::Submitted(round) => Ok(Response::new()
+            .add_attribute("action", "submit_rates")
+            .add_attributes(submitted_attributes(&denom, &round))),
+    }
+}
+
+pub fn post_rates_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    updates: Vec<RateUpdate>,
+) -> Result<Response, ContractError> {
+    ensure!(!updates.is_empty(), ContractError::EmptyBatch {});
+
+    let count = updates.len();
+    let mut events = Vec::with_capacity(count);
+    for update in updates {
+        let denom = update.denom.clone();
+        let event = match update_rates(deps.branch(), &env, &info.sender, update)? {
+            PostedRates::Stored(rates) => {
+                Event::new("post_rates").add_attributes(rates_attributes(&denom, &rates))
+            }
+            PostedRates::Quarantined(rates, limit) => {
+                quarantined_event(&denom, &rates, &info.sender, limit)
+            }
+            PostedRates::Submitted(round) => {
+                Event::new("submit_rates").add_attributes(submitted_attributes(&denom, &round))
+            }
+        };
+        events.push(event);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "post_rates_batch")
+        .add_attribute("count", count.to_string())
+        .add_events(events))
+}
+
+/// Validates and stores a rate update, or quarantines it if it is out of band
+fn update_rates(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    update: RateUpdate,
+) -> Result<PostedRates, ContractError> {
+    let RateUpdate {
+        denom,
+        purchase_rate,
+        redemption_rate,
+        slashing,
+        source_update_time,
+        source_block_height,
+    } = update;
+
+    let slashing = slashing.unwrap_or(false);
+    if slashing {
+        // Slashing events can only be signed by the admin
+        assert_admin(deps.storage, sender)?;
+    } else {
+        ensure!(
+            FEEDERS.has(deps.storage, sender),
+            ContractError::Unauthorized {}
+        );
+    }
+
     ensure!(
-        info.sender == config.admin_address,
-        ContractError::Unauthorized {}
+        DENOMS.has(deps.storage, &denom),
+        ContractError::DenomNotRegistered { denom }
+    );
+    ensure!(
+        !PAUSED.may_load(deps.storage)?.unwrap_or(false),
+        ContractError::Paused {}
+    );
+    ensure!(
+        !PAUSED_DENOMS.has(deps.storage, &denom),
+        ContractError::DenomPaused { denom }
     );
 
     let block_height = env.block.height;
     let update_time = env.block.time.seconds();
 
-    let purchase_rate = Decimal::from_str(&purchase_rate).unwrap();
-    let redemption_rate = Decimal::from_str(&redemption_rate).unwrap();
+    ensure!(
+        purchase_rate.is_some() || redemption_rate.is_some(),
+        ContractError::NoRates {}
+    );
+    // A slashing event is recorded in the redemption rates history
+    ensure!(
+        !slashing || redemption_rate.is_some(),
+        ContractError::SlashingWithoutRedemptionRate { denom }
+    );
+    check_source(
+        deps.as_ref(),
+        env,
+        &denom,
+        source_update_time,
+        source_block_height,
+    )?;
+    let config = CONFIG.load(deps.storage)?;
+    let purchase_rate = purchase_rate
+        .map(|v| parse_rate("purchase_rate", &v, config.max_rate))
+        .transpose()?;
+    let redemption_rate = redemption_rate
+        .map(|v| parse_rate("redemption_rate", &v, config.max_rate))
+        .transpose()?;
+
+    let rates = match AGGREGATIONS.may_load(deps.storage, &denom)? {
+        // Slashing events signed by the admin bypass the aggregation
+        Some(aggregation) if !slashing => {
+            let (Some(purchase_rate), Some(redemption_rate)) = (purchase_rate, redemption_rate)
+            else {
+                return Err(ContractError::IncompleteSubmission { denom });
+            };
+            let round = submit_to_round(
+                deps.storage,
+                &denom,
+                &aggregation,
+                Submission {
+                    feeder: sender.clone(),
+                    purchase_rate,
+                    redemption_rate,
+                    update_time,
+                    source_update_time,
+                    source_block_height,
+                },
+            )?;
+            if !round.finalized {
+                return Ok(PostedRates::Submitted(round));
+            }
+            // Later submissions must be newer than every observation of the round
+            record_source(
+                deps.storage,
+                &denom,
+                round
+                    .submissions
+                    .iter()
+                    .filter_map(|v| v.source_update_time)
+                    .max(),
+                round
+                    .submissions
+                    .iter()
+                    .filter_map(|v| v.source_block_height)
+                    .max(),
+            )?;
+            Rates {
+                purchase_rate: median(round.submissions.iter().map(|v| v.purchase_rate)),
+                redemption_rate: median(round.submissions.iter().map(|v| v.redemption_rate)),
+                update_time,
+                block_height,
+                // The median is attributed to the contract rather than to the
+                // feeder completing the round
+                feeder: env.contract.address.clone(),
+                // Each submission observed the host chain at its own point
+                source_update_time: None,
+                source_block_height: None,
+                slashing,
+                purchase_rate_carried_from: None,
+                redemption_rate_carried_from: None,
+            }
+        }
+        _ => {
+            let mut rates = carry_latest_rates(
+                deps.as_ref(),
+                env,
+                sender,
+                &denom,
+                purchase_rate,
+                redemption_rate,
+                slashing,
+            )?;
+            rates.source_update_time = source_update_time;
+            rates.source_block_height = source_block_height;
+            rates
+        }
+    };
+    assert_no_rates_at(deps.storage, &denom, block_height)?;
+    check_rate_policy(deps.as_ref(), &denom, &rates)?;
+
+    // Out-of-band rates are kept aside until the admin confirms them
+    if let Some(limit) = check_deviation(deps.as_ref(), &denom, &rates)? {
+        QUARANTINED_RATES.save(
+            deps.storage,
+            &denom,
+            &QuarantinedRates {
+                rates: rates.clone(),
+            },
+        )?;
+        return Ok(PostedRates::Quarantined(rates, limit));
+    }
+
+    save_rates(deps.storage, &denom, &rates, update_time)?;
+    // In-band rates supersede the quarantined ones
+    QUARANTINED_RATES.remove(deps.storage, &denom);
+
+    Ok(PostedRates::Stored(rates))
+}
+
+/// Builds the rates of an update, the rates left unset keeping their latest value
+fn carry_latest_rates(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    denom: &str,
+    purchase_rate: Option<Decimal>,
+    redemption_rate: Option<Decimal>,
+    slashing: bool,
+) -> Result<Rates, ContractError> {
+    let latest = get_latest_rates(deps, denom.to_string())?;
+    let missing_rate = |field: &str| ContractError::MissingRate {
+        field: field.to_string(),
+        denom: denom.to_string(),
+    };
+    let (purchase_rate, purchase_rate_carried_from) = match (purchase_rate, &latest) {
+        (Some(purchase_rate), _) => (purchase_rate, None),
+        (None, Some(latest)) => (latest.purchase_rate, Some(latest.purchase_rate_origin())),
+        (None, None) => return Err(missing_rate("purchase_rate")),
+    };
+    let (redemption_rate, redemption_rate_carried_from) = match (redemption_rate, &latest) {
+        (Some(redemption_rate), _) => (redemption_rate, None),
+        (None, Some(latest)) => (
+            latest.redemption_rate,
+            Some(latest.redemption_rate_origin()),
+        ),
+        (None, None) => return Err(missing_rate("redemption_rate")),
+    };
 
-    let rates = Rates {
+    Ok(Rates {
         purchase_rate,
         redemption_rate,
-        update_time,
-    };
-    RATES.save(deps.storage, (&denom, block_height), &rates)?;
-
-    let prefix = RATES.prefix(&denom);
-    // Truncate the rates
-    let count = prefix
-        .range(deps.storage, None, None, Order::Ascending)
-        .count();
-    if count > MAX_NUM_HISTORICAL_RATES {
-        let first = prefix
-            .range(deps.storage, None, None, Order::Ascending)
-            .take(1)
-            .next()
-            .unwrap()?;
-        RATES.remove(deps.storage, (&denom, first.0));
+        update_time: env.block.time.seconds(),
+        block_height: env.block.height,
+        feeder: sender.clone(),
+        source_update_time: None,
+        source_block_height: None,
+        slashing,
+        purchase_rate_carried_from,
+        redemption_rate_carried_from,
+    })
+}
+
+/// Adds a feeder's submission to the open round of a denom, starting a new
+/// round if the latest one is finalized or its window elapsed, and dropping
+/// the oldest one kept. The round is finalized once the quorum is reached
+fn submit_to_round(
+    storage: &mut dyn Storage,
+    denom: &str,
+    aggregation: &Aggregation,
+    submission: Submission,
+) -> Result<AggregationRound, ContractError> {
+    let now = submission.update_time;
+    let mut round = match get_latest_round(storage, denom)? {
+        Some(round)
+            if !round.finalized
+                && now <= round.start_time.saturating_add(aggregation.window_seconds) =>
+        {
+            round
+        }
+        latest => {
+            let id = latest.map_or(1, |round| round.id + 1);
+            if id > MAX_NUM_AGGREGATION_ROUNDS {
+                AGGREGATION_ROUNDS.remove(storage, (denom, id - MAX_NUM_AGGREGATION_ROUNDS));
+            }
+            AggregationRound {
+                id,
+                start_time: now,
+                submissions: vec![],
+                finalized: false,
+            }
+        }
+    };
+
+    ensure!(
+        !round
+            .submissions
+            .iter()
+            .any(|v| v.feeder == submission.feeder),
+        ContractError::AlreadySubmitted {
+            denom: denom.to_string(),
+            round: round.id,
+        }
+    );
+    round.submissions.push(submission);
+    round.finalized = round.submissions.len() >= aggregation.quorum as usize;
+    AGGREGATION_ROUNDS.save(storage, (denom, round.id), &round)?;
+
+    Ok(round)
+}
+
+/// Removes the aggregation rounds kept for a denom
+fn clear_aggregation_rounds(storage: &mut dyn Storage, denom: &str) -> StdResult<()> {
+    let ids = AGGREGATION_ROUNDS
+        .prefix(denom)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in ids {
+        AGGREGATION_ROUNDS.remove(storage, (denom, id));
+    }
+    Ok(())
+}
+
+/// Median of the rates, averaging the two middle ones for an even count
+fn median(rates: impl Iterator<Item = Decimal>) -> Decimal {
+    let mut rates: Vec<Decimal> = rates.collect();
+    rates.sort();
+
+    let mid = rates.len() / 2;
+    if rates.len() % 2 == 1 {
+        return rates[mid];
+    }
+    // Halving the difference rather than the sum cannot overflow
+    rates[mid - 1] + (rates[mid] - rates[mid - 1]) / Decimal::from_ratio(2u8, 1u8)
+}
+
+/// Attributes of the posted rates, leaving out the ones carried over
+fn rates_attributes(denom: &str, rates: &Rates) -> Vec<Attribute> {
+    let mut attributes = vec![attr("denom", denom)];
+    if rates.purchase_rate_carried_from.is_none() {
+        attributes.push(attr("purchase_rate", rates.purchase_rate.to_string()));
+    }
+    if rates.redemption_rate_carried_from.is_none() {
+        attributes.push(attr("redemption_rate", rates.redemption_rate.to_string()));
+    }
+    attributes.push(attr("update_time", rates.update_time.to_string()));
+    if let Some(source_update_time) = rates.source_update_time {
+        attributes.push(attr("source_update_time", source_update_time.to_string()));
+    }
+    if let Some(source_block_height) = rates.source_block_height {
+        attributes.push(attr("source_block_height", source_block_height.to_string()));
+    }
+    attributes.push(attr("slashing", rates.slashing.to_string()));
+    attributes
+}
+
+fn submitted_attributes(denom: &str, round: &AggregationRound) -> Vec<Attribute> {
+    vec![
+        attr("denom", denom),
+        attr("round", round.id.to_string()),
+        attr("submissions", round.submissions.len().to_string()),
+    ]
+}
+
+fn quarantined_event(denom: &str, rates: &Rates, feeder: &Addr, limit: &str) -> Event {
+    Event::new("rates_quarantined")
+        .add_attribute("denom", denom)
+        .add_attribute("purchase_rate", rates.purchase_rate.to_string())
+        .add_attribute("redemption_rate", rates.redemption_rate.to_string())
+        .add_attribute("update_time", rates.update_time.to_string())
+        .add_attribute("feeder", feeder)
+        .add_attribute("limit", limit)
+}
+
+pub fn add_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let feeder = validate_address(deps.api, &address)?;
+    ensure!(
+        !FEEDERS.has(deps.storage, &feeder),
+        ContractError::FeederAlreadyRegistered { address }
+    );
+    FEEDERS.save(deps.storage, &feeder, &Empty {})?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_feeder")
+        .add_attribute("feeder", feeder))
+}
+
+pub fn remove_feeder(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let feeder = validate_address(deps.api, &address)?;
+    ensure!(
+        FEEDERS.has(deps.storage, &feeder),
+        ContractError::FeederNotRegistered { address }
+    );
+
+    // Every aggregation must stay reachable by the remaining feeders
+    let num_feeders = FEEDERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count()
+        - 1;
+    for item in AGGREGATIONS.range(deps.storage, None, None, Order::Ascending) {
+        let (denom, aggregation) = item?;
+        ensure!(
+            aggregation.quorum as usize <= num_feeders,
+            ContractError::InvalidAggregation {
+                reason: format!("quorum of {denom} exceeds the {num_feeders} remaining feeders")
+            }
+        );
+    }
+    FEEDERS.remove(deps.storage, &feeder);
+
+    // Submissions of the removed feeder no longer count toward open rounds
+    let denoms = AGGREGATIONS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in denoms {
+        if let Some(mut round) = get_latest_round(deps.storage, &denom)? {
+            if !round.finalized {
+                round.submissions.retain(|v| v.feeder != feeder);
+                AGGREGATION_ROUNDS.save(deps.storage, (&denom, round.id), &round)?;
+            }
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_feeder")
+        .add_attribute("feeder", feeder))
+}
+
+pub fn register_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    metadata: DenomMetadata,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    validate_denom_metadata(&denom, &metadata)?;
+    ensure!(
+        !DENOMS.has(deps.storage, &denom),
+        ContractError::DenomAlreadyRegistered { denom }
+    );
+    DENOMS.save(deps.storage, &denom, &metadata)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_denom")
+        .add_attribute("denom", denom))
+}
+
+pub fn update_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    metadata: DenomMetadata,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    validate_denom_metadata(&denom, &metadata)?;
+    ensure!(
+        DENOMS.has(deps.storage, &denom),
+        ContractError::DenomNotRegistered { denom }
+    );
+    DENOMS.save(deps.storage, &denom, &metadata)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_denom")
+        .add_attribute("denom", denom))
+}
+
+pub fn deregister_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    ensure!(
+        DENOMS.has(deps.storage, &denom),
+        ContractError::DenomNotRegistered { denom }
+    );
+    DENOMS.remove(deps.storage, &denom);
+    QUARANTINED_RATES.remove(deps.storage, &denom);
+    clear_aggregation_rounds(deps.storage, &denom)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deregister_denom")
+        .add_attribute("denom", denom))
+}
+
+pub fn propose_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    expiry: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let address = validate_address(deps.api, &address)?;
+    if let Some(expiry) = expiry {
+        ensure!(
+            expiry > env.block.time.seconds(),
+            ContractError::InvalidExpiry { expiry }
+        );
+    }
+    PENDING_ADMIN.save(
+        deps.storage,
+        &PendingAdmin {
+            address: address.clone(),
+            expiry,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_admin")
+        .add_attribute("pending_admin", address)
+        .add_attribute(
+            "expiry",
+            expiry.map_or("none".to_string(), |v| v.to_string()),
+        ))
+}
+
+pub fn accept_admin(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    ensure!(
+        info.sender == pending_admin.address,
+        ContractError::Unauthorized {}
+    );
+    if let Some(expiry) = pending_admin.expiry {
+        ensure!(
+            env.block.time.seconds() < expiry,
+            ContractError::AdminProposalExpired {}
+        );
     }
 
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.admin_address = pending_admin.address.clone();
+        Ok(config)
+    })?;
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin_address", pending_admin.address))
+}
+
+pub fn cancel_admin_proposal(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    ensure!(
+        PENDING_ADMIN.exists(deps.storage),
+        ContractError::NoPendingAdmin {}
+    );
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "cancel_admin_proposal"))
+}
+
+pub fn set_max_rate(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_rate: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config = assert_admin(deps.storage, &info.sender)?;
+
+    validate_max_rate(max_rate)?;
+    config.max_rate = max_rate;
+    CONFIG.save(deps.storage, &config)?;
+
     Ok(Response::new()
-        .add_attribute("action", "post_rates")
+        .add_attribute("action", "set_max_rate")
+        .add_attribute(
+            "max_rate",
+            max_rate.map_or("none".to_string(), |v| v.to_string()),
+        ))
+}
+
+pub fn set_deviation_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    limits: Option<DeviationLimits>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match limits {
+        Some(limits) => {
+            ensure!(
+                limits.max_change_per_window.is_none() || limits.window_seconds > 0,
+                ContractError::InvalidDeviationLimits {}
+            );
+            DEVIATION_LIMITS.save(deps.storage, &denom, &limits)?;
+        }
+        None => DEVIATION_LIMITS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_deviation_limits")
+        .add_attribute("denom", denom))
+}
+
+pub fn set_rate_policy(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    policy: Option<RatePolicy>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match policy {
+        Some(policy) => RATE_POLICIES.save(deps.storage, &denom, &policy)?,
+        None => RATE_POLICIES.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_rate_policy")
+        .add_attribute("denom", denom))
+}
+
+pub fn set_max_staleness(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    max_staleness_seconds: Option<u64>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match max_staleness_seconds {
+        Some(max_staleness_seconds) => {
+            MAX_STALENESS.save(deps.storage, &denom, &max_staleness_seconds)?
+        }
+        None => MAX_STALENESS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_max_staleness")
+        .add_attribute("denom", denom)
+        .add_attribute(
+            "max_staleness_seconds",
+            max_staleness_seconds.map_or("none".to_string(), |v| v.to_string()),
+        ))
+}
+
+pub fn set_retention(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    retention: Option<Retention>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match retention {
+        Some(retention) => {
+            validate_retention(&retention)?;
+            RETENTION.save(deps.storage, &denom, &retention)?;
+        }
+        None => RETENTION.remove(deps.storage, &denom),
+    }
+    if let Some(mut index) = RATES_INDEX.may_load(deps.storage, &denom)? {
+        prune_rates(deps.storage, &denom, &mut index, env.block.time.seconds())?;
+        RATES_INDEX.save(deps.storage, &denom, &index)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_retention")
+        .add_attribute("denom", denom))
+}
+
+pub fn confirm_quarantined_rates(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    let quarantined = QUARANTINED_RATES
+        .may_load(deps.storage, &denom)?
+        .ok_or_else(|| ContractError::NoQuarantinedRates {
+            denom: denom.clone(),
+        })?;
+    QUARANTINED_RATES.remove(deps.storage, &denom);
+    assert_no_rates_at(deps.storage, &denom, env.block.height)?;
+
+    // The rates become effective from the confirmation, the time they were
+    // posted at being reported alongside
+    let mut rates = quarantined.rates;
+    let posted_time = rates.update_time;
+    rates.block_height = env.block.height;
+    rates.update_time = env.block.time.seconds();
+    save_rates(deps.storage, &denom, &rates, rates.update_time)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "confirm_quarantined_rates")
         .add_attribute("denom", denom)
-        .add_attribute("purchase_rate", purchase_rate.to_string())
-        .add_attribute("redemption_rate", redemption_rate.to_string())
-        .add_attribute("update_time", update_time.to_string()))
+        .add_attribute("purchase_rate", rates.purchase_rate.to_string())
+        .add_attribute("redemption_rate", rates.redemption_rate.to_string())
+        .add_attribute("update_time", rates.update_time.to_string())
+        .add_attribute("posted_time", posted_time.to_string()))
+}
+
+pub fn reject_quarantined_rates(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    ensure!(
+        QUARANTINED_RATES.has(deps.storage, &denom),
+        ContractError::NoQuarantinedRates {
+            denom: denom.clone()
+        }
+    );
+    QUARANTINED_RATES.remove(deps.storage, &denom);
+
+    Ok(Response::new()
+        .add_attribute("action", "reject_quarantined_rates")
+        .add_attribute("denom", denom))
+}
+
+pub fn set_aggregation(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    aggregation: Option<Aggregation>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match aggregation {
+        Some(aggregation) => {
+            validate_aggregation(&aggregation)?;
+            let num_feeders = FEEDERS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .count();
+            ensure!(
+                aggregation.quorum as usize <= num_feeders,
+                ContractError::InvalidAggregation {
+                    reason: format!("quorum exceeds the {num_feeders} registered feeders")
+                }
+            );
+            AGGREGATIONS.save(deps.storage, &denom, &aggregation)?;
+        }
+        None => {
+            AGGREGATIONS.remove(deps.storage, &denom);
+            clear_aggregation_rounds(deps.storage, &denom)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_aggregation")
+        .add_attribute("denom", denom))
+}
+
+pub fn pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure!(
+        info.sender == config.admin_address || Some(&info.sender) == config.guardian.as_ref(),
+        ContractError::Unauthorized {}
+    );
+
+    match &denom {
+        Some(denom) => {
+            ensure!(
+                DENOMS.has(deps.storage, denom),
+                ContractError::DenomNotRegistered {
+                    denom: denom.clone()
+                }
+            );
+            PAUSED_DENOMS.save(deps.storage, denom, &Empty {})?;
+        }
+        None => PAUSED.save(deps.storage, &true)?,
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "pause")
+        .add_attribute("denom", denom.unwrap_or("all".to_string()))
+        .add_attribute("sender", info.sender))
+}
+
+pub fn unpause(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    assert_admin(deps.storage, &info.sender)?;
+
+    match &denom {
+        Some(denom) => PAUSED_DENOMS.remove(deps.storage, denom),
+        None => PAUSED.remove(deps.storage),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "unpause")
+        .add_attribute("denom", denom.unwrap_or("all".to_string())))
+}
+
+pub fn set_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = assert_admin(deps.storage, &info.sender)?;
+
+    config.guardian = address
+        .map(|address| validate_address(deps.api, &address))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_guardian")
+        .add_attribute(
+            "guardian",
+            config
+                .guardian
+                .map_or("none".to_string(), |v| v.to_string()),
+        ))
+}
+
+pub fn set_price_source(
+    deps: DepsMut,
+    info: MessageInfo,
+    price_source: Option<PriceSourceInfo>,
+) -> Result<Response, ContractError> {
+    let mut config = assert_admin(deps.storage, &info.sender)?;
+
+    config.price_source = match price_source {
+        Some(price_source) => {
+            ensure!(
+                price_source.max_price_age_seconds > 0,
+                ContractError::InvalidPriceSource {}
+            );
+            Some(PriceSource {
+                address: validate_address(deps.api, &price_source.address)?,
+                max_price_age_seconds: price_source.max_price_age_seconds,
+            })
+        }
+        None => None,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    let (address, max_price_age_seconds) = match config.price_source {
+        Some(v) => (v.address.to_string(), v.max_price_age_seconds.to_string()),
+        None => ("none".to_string(), "none".to_string()),
+    };
+    Ok(Response::new()
+        .add_attribute("action", "set_price_source")
+        .add_attribute("price_source", address)
+        .add_attribute("max_price_age_seconds", max_price_age_seconds))
+}
+
+/// Saves the rates and prunes the denom's history
+fn save_rates(storage: &mut dyn Storage, denom: &str, rates: &Rates, now: u64) -> StdResult<()> {
+    let block_height = rates.block_height;
+    let mut index = match RATES_INDEX.may_load(storage, denom)? {
+        Some(mut index) => {
+            index.count += 1;
+            index.oldest = index.oldest.min(block_height);
+            index.newest = index.newest.max(block_height);
+            index
+        }
+        None => RatesIndex {
+            count: 1,
+            oldest: block_height,
+            newest: block_height,
+        },
+    };
+    RATES.save(storage, (denom, block_height), rates)?;
+
+    record_source(
+        storage,
+        denom,
+        rates.source_update_time,
+        rates.source_block_height,
+    )?;
+
+    prune_rates(storage, denom, &mut index, now)?;
+    RATES_INDEX.save(storage, denom, &index)
+}
+
+/// Records the host chain observation later rates must be newer than
+fn record_source(
+    storage: &mut dyn Storage,
+    denom: &str,
+    update_time: Option<u64>,
+    block_height: Option<u64>,
+) -> StdResult<()> {
+    if update_time.is_none() && block_height.is_none() {
+        return Ok(());
+    }
+    let mut source = LATEST_SOURCES.may_load(storage, denom)?.unwrap_or_default();
+    source.update_time = update_time.or(source.update_time);
+    source.block_height = block_height.or(source.block_height);
+    LATEST_SOURCES.save(storage, denom, &source)
+}
+
+/// Removes the oldest rates exceeding the denom's retention, one at a time
+/// so that the cost only depends on the number of removed rates
+fn prune_rates(
+    storage: &mut dyn Storage,
+    denom: &str,
+    index: &mut RatesIndex,
+    now: u64,
+) -> StdResult<()> {
+    let retention = RETENTION.may_load(storage, denom)?.unwrap_or_default();
+    let cutoff = retention
+        .max_age_seconds
+        .map(|max_age_seconds| now.saturating_sub(max_age_seconds));
+
+    // The latest rates are always kept
+    while index.count > 1 {
+        let exceeds_count = retention
+            .max_count
+            .is_some_and(|max_count| index.count > max_count.into());
+        let expired = match cutoff {
+            Some(cutoff) => RATES.load(storage, (denom, index.oldest))?.update_time < cutoff,
+            None => false,
+        };
+        if !exceeds_count && !expired {
+            break;
+        }
+
+        RATES.remove(storage, (denom, index.oldest));
+        index.count -= 1;
+        index.oldest = RATES
+            .prefix(denom)
+            .keys(
+                storage,
+                Some(Bound::exclusive(index.oldest)),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?
+            .unwrap_or(index.newest);
+    }
+
+    Ok(())
+}
+
+/// Rejects a second update of a denom's rates in the same block, instead of
+/// overwriting the first one
+fn assert_no_rates_at(
+    storage: &dyn Storage,
+    denom: &str,
+    block_height: u64,
+) -> Result<(), ContractError> {
+    ensure!(
+        !RATES.has(storage, (denom, block_height)),
+        ContractError::DuplicateRates {
+            denom: denom.to_string(),
+            block_height,
+        }
+    );
+    Ok(())
+}
+
+/// Validates the host chain observation supplied by the feeder against the
+/// current time and the latest observation stored for the denom, even if the
+/// latest rates were posted without one
+fn check_source(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    source_update_time: Option<u64>,
+    source_block_height: Option<u64>,
+) -> Result<(), ContractError> {
+    if let Some(source_update_time) = source_update_time {
+        ensure!(
+            source_update_time <= env.block.time.seconds(),
+            ContractError::SourceTimeInFuture { source_update_time }
+        );
+    }
+
+    let latest = LATEST_SOURCES
+        .may_load(deps.storage, denom)?
+        .unwrap_or_default();
+    for (field, value, previous) in [
+        ("source_update_time", source_update_time, latest.update_time),
+        (
+            "source_block_height",
+            source_block_height,
+            latest.block_height,
+        ),
+    ] {
+        if let (Some(value), Some(previous)) = (value, previous) {
+            ensure!(
+                value > previous,
+                ContractError::SourceNotNewer {
+                    field: field.to_string(),
+                    value,
+                    previous,
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Enforces the denom's rate policy against the latest rates
+fn check_rate_policy(deps: Deps, denom: &str, rates: &Rates) -> Result<(), ContractError> {
+    let policy = match RATE_POLICIES.may_load(deps.storage, denom)? {
+        Some(policy) => policy,
+        None => return Ok(()),
+    };
+
+    if policy.non_decreasing_redemption_rate && !rates.slashing {
+        if let Some(latest) = get_latest_rates(deps, denom.to_string())? {
+            ensure!(
+                rates.redemption_rate >= latest.redemption_rate,
+                ContractError::RedemptionRateDecreased {
+                    denom: denom.to_string(),
+                    previous: latest.redemption_rate,
+                    current: rates.redemption_rate,
+                }
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the deviation limit exceeded by the rates, if any
+fn check_deviation(deps: Deps, denom: &str, rates: &Rates) -> StdResult<Option<&'static str>> {
+    let limits = match DEVIATION_LIMITS.may_load(deps.storage, denom)? {
+        Some(limits) => limits,
+        None => return Ok(None),
+    };
+
+    if let Some(max_change) = limits.max_change_per_update {
+        if let Some(latest) = get_latest_rates(deps, denom.to_string())? {
+            if exceeds_deviation(&latest, rates, max_change) {
+                return Ok(Some("max_change_per_update"));
+            }
+        }
+    }
+
+    if let Some(max_change) = limits.max_change_per_window {
+        let window_start = rates.update_time.saturating_sub(limits.window_seconds);
+        for item in RATES
+            .prefix(denom)
+            .range(deps.storage, None, None, Order::Descending)
+        {
+            let (_, previous) = item?;
+            if previous.update_time < window_start {
+                break;
+            }
+            if exceeds_deviation(&previous, rates, max_change) {
+                return Ok(Some("max_change_per_window"));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+fn exceeds_deviation(previous: &Rates, rates: &Rates, max_change: Decimal) -> bool {
+    let exceeds = |previous: Decimal, current: Decimal| {
+        let max_diff = previous.checked_mul(max_change).unwrap_or(Decimal::MAX);
+        previous.abs_diff(current) > max_diff
+    };
+    exceeds(previous.purchase_rate, rates.purchase_rate)
+        || exceeds(previous.redemption_rate, rates.redemption_rate)
+}
+
+fn assert_admin(storage: &dyn Storage, sender: &Addr) -> Result<Config, ContractError> {
+    let config = CONFIG.load(storage)?;
+    ensure!(
+        *sender == config.admin_address,
+        ContractError::Unauthorized {}
+    );
+    Ok(config)
 }
@@ -0,0 +1,113 @@
+use crate::error::ContractError;
+use crate::msg::{DenomInfo, MigrateMsg};
+use crate::state::{Config, Rates, RatesIndex, CONFIG, DENOMS, FEEDERS, RATES, RATES_INDEX};
+use crate::validation::{validate_address, validate_denom_metadata};
+use cosmwasm_std::{ensure, DepsMut, Empty, Order, StdResult};
+use semver::Version;
+
+/// State as stored up to v1.0.0
+pub mod v1_0_0 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Addr, Decimal};
+    use cw_storage_plus::{Item, Map};
+
+    pub const CONFIG: Item<Config> = Item::new("config");
+
+    pub const RATES: Map<(&str, u64), Rates> = Map::new("rates");
+
+    #[cw_serde]
+    pub struct Config {
+        pub admin_address: Addr,
+    }
+
+    #[cw_serde]
+    pub struct Rates {
+        pub purchase_rate: Decimal,
+        pub redemption_rate: Decimal,
+        /// Unix timestamp
+        pub update_time: u64,
+    }
+}
+
+/// Migrates the stored state from `version`, running in order every migration
+/// to a newer version, then applies the options of the message
+pub fn migrate_state(
+    mut deps: DepsMut,
+    version: &Version,
+    msg: MigrateMsg,
+) -> Result<(), ContractError> {
+    if *version < Version::new(1, 1, 0) {
+        migrate_to_v1_1_0(deps.branch())?;
+    }
+
+    for address in msg.feeders.unwrap_or_default() {
+        let feeder = validate_address(deps.api, &address)?;
+        ensure!(
+            !FEEDERS.has(deps.storage, &feeder),
+            ContractError::FeederAlreadyRegistered { address }
+        );
+        FEEDERS.save(deps.storage, &feeder, &Empty {})?;
+    }
+    for DenomInfo { denom, metadata } in msg.denoms.unwrap_or_default() {
+        validate_denom_metadata(&denom, &metadata)?;
+        ensure!(
+            !DENOMS.has(deps.storage, &denom),
+            ContractError::DenomAlreadyRegistered { denom }
+        );
+        DENOMS.save(deps.storage, &denom, &metadata)?;
+    }
+
+    Ok(())
+}
+
+/// Fills in the fields added to the config and the rates, the rates being
+/// attributed to the admin, which was the only one allowed to post them, and
+/// indexes the rates history of every denom
+fn migrate_to_v1_1_0(deps: DepsMut) -> Result<(), ContractError> {
+    let legacy_config = v1_0_0::CONFIG.load(deps.storage)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin_address: legacy_config.admin_address.clone(),
+            max_rate: None,
+            guardian: None,
+            price_source: None,
+        },
+    )?;
+
+    let legacy_rates = v1_0_0::RATES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((denom, block_height), legacy) in legacy_rates {
+        let rates = Rates {
+            purchase_rate: legacy.purchase_rate,
+            redemption_rate: legacy.redemption_rate,
+            update_time: legacy.update_time,
+            block_height,
+            feeder: legacy_config.admin_address.clone(),
+            source_update_time: None,
+            source_block_height: None,
+            slashing: false,
+            purchase_rate_carried_from: None,
+            redemption_rate_carried_from: None,
+        };
+        RATES.save(deps.storage, (&denom, block_height), &rates)?;
+
+        // Rates are visited by ascending block height within each denom
+        let index = match RATES_INDEX.may_load(deps.storage, &denom)? {
+            Some(index) => RatesIndex {
+                count: index.count + 1,
+                newest: block_height,
+                ..index
+            },
+            None => RatesIndex {
+                count: 1,
+                oldest: block_height,
+                newest: block_height,
+            },
+        };
+        RATES_INDEX.save(deps.storage, &denom, &index)?;
+    }
+
+    Ok(())
+}
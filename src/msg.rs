@@ -1,37 +1,257 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, Decimal};
+use cosmwasm_std::{Addr, Binary, Decimal, Order};
+
+use crate::state::{Aggregation, DenomMetadata, DeviationLimits, RatePolicy, Retention};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub admin_address: String,
+    /// Sanity ceiling applied to every posted rate
+    pub max_rate: Option<Decimal>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Posts the rates of a denom. A rate left unset keeps its latest value.
+    /// Rates can be posted once per denom and block
     PostRates {
         denom: String,
-        purchase_rate: String,
-        redemption_rate: String,
+        purchase_rate: Option<String>,
+        redemption_rate: Option<String>,
+        /// Flags the update as a slashing event, only allowed for the admin
+        /// and with a redemption rate
+        slashing: Option<bool>,
+        /// Unix timestamp of the observation on the host chain, which must
+        /// not be in the future and must be newer than the previous one
+        source_update_time: Option<u64>,
+        /// Block height of the observation on the host chain, which must be
+        /// higher than the previous one
+        source_block_height: Option<u64>,
+    },
+
+    /// Posts the rates of multiple denoms at once, failing if any of them is
+    /// invalid
+    PostRatesBatch { rates: Vec<RateUpdate> },
+
+    /// Allows an address to post rates, only callable by the admin
+    AddFeeder { address: String },
+
+    /// Revokes an address' permission to post rates, as long as the remaining
+    /// feeders can reach the quorum of every aggregation. Its submissions to
+    /// rounds still open are dropped. Only callable by the admin
+    RemoveFeeder { address: String },
+
+    /// Registers a denom for which rates can be posted, only callable by the
+    /// admin
+    RegisterDenom {
+        denom: String,
+        metadata: DenomMetadata,
+    },
+
+    /// Updates the metadata of a registered denom, only callable by the admin
+    UpdateDenom {
+        denom: String,
+        metadata: DenomMetadata,
+    },
+
+    /// Stops accepting rates for a denom, keeping its history. Only callable
+    /// by the admin
+    DeregisterDenom { denom: String },
+
+    /// Proposes a new admin, which has to accept the ownership before the
+    /// optional expiry (unix timestamp). Only callable by the admin
+    ProposeAdmin {
+        address: String,
+        expiry: Option<u64>,
+    },
+
+    /// Accepts the ownership, only callable by the pending admin
+    AcceptAdmin {},
+
+    /// Cancels the pending admin proposal, only callable by the admin
+    CancelAdminProposal {},
+
+    /// Sets or removes the sanity ceiling applied to every posted rate, only
+    /// callable by the admin
+    SetMaxRate { max_rate: Option<Decimal> },
+
+    /// Sets or removes the maximum rate changes accepted for a denom. Rates
+    /// outside of the limits are quarantined until the admin confirms them.
+    /// Only callable by the admin
+    SetDeviationLimits {
+        denom: String,
+        limits: Option<DeviationLimits>,
+    },
+
+    /// Sets or removes the rate policy enforced for a denom, only callable by
+    /// the admin
+    SetRatePolicy {
+        denom: String,
+        policy: Option<RatePolicy>,
+    },
+
+    /// Sets or removes the maximum age, in seconds, after which the latest
+    /// rates of a denom are reported as stale. Only callable by the admin
+    SetMaxStaleness {
+        denom: String,
+        max_staleness_seconds: Option<u64>,
+    },
+
+    /// Sets how long the rates history of a denom is kept, pruning the
+    /// rates exceeding it. `None` restores the default of keeping the
+    /// latest 100 rates. Only callable by the admin
+    SetRetention {
+        denom: String,
+        retention: Option<Retention>,
+    },
+
+    /// Stores the quarantined rates of a denom, effective from the
+    /// confirmation. Only callable by the admin
+    ConfirmQuarantinedRates { denom: String },
+
+    /// Discards the quarantined rates of a denom, only callable by the admin
+    RejectQuarantinedRates { denom: String },
+
+    /// Sets or removes the aggregation of a denom's rates. While set, feeder
+    /// posts are submissions to a round, whose median rates are stored once
+    /// the quorum is reached. The quorum cannot exceed the number of
+    /// registered feeders. Only callable by the admin
+    SetAggregation {
+        denom: String,
+        aggregation: Option<Aggregation>,
+    },
+
+    /// Pauses posting rates for a denom, or for every denom if unset. Only
+    /// callable by the admin or the guardian
+    Pause { denom: Option<String> },
+
+    /// Unpauses posting rates for a denom, or for every denom if unset. Only
+    /// callable by the admin
+    Unpause { denom: Option<String> },
+
+    /// Sets or removes the guardian, only callable by the admin
+    SetGuardian { address: Option<String> },
+
+    /// Sets or removes the contract queried for the prices of the underlying
+    /// assets, with the maximum age of the prices it returns. Only callable
+    /// by the admin
+    SetPriceSource {
+        price_source: Option<PriceSourceInfo>,
     },
 }
 
+#[cw_serde]
+pub struct RateUpdate {
+    pub denom: String,
+    /// Keeps the latest purchase rate if unset
+    pub purchase_rate: Option<String>,
+    /// Keeps the latest redemption rate if unset
+    pub redemption_rate: Option<String>,
+    /// Flags the update as a slashing event, only allowed for the admin
+    /// and with a redemption rate
+    pub slashing: Option<bool>,
+    /// Unix timestamp of the observation on the host chain
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain
+    pub source_block_height: Option<u64>,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(crate::state::Config)]
     Config {},
 
+    #[returns(Option<crate::state::PendingAdmin>)]
+    PendingAdmin {},
+
+    #[returns(FeedersResponse)]
+    Feeders {},
+
+    #[returns(DenomsResponse)]
+    Denoms {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(Option<crate::state::DeviationLimits>)]
+    DeviationLimits { denom: String },
+
+    #[returns(Option<u64>)]
+    MaxStaleness { denom: String },
+
+    #[returns(crate::state::Retention)]
+    Retention { denom: String },
+
+    #[returns(Option<crate::state::RatePolicy>)]
+    RatePolicy { denom: String },
+
+    #[returns(Option<crate::state::QuarantinedRates>)]
+    QuarantinedRates { denom: String },
+
+    /// Returns whether posting rates is paused for a denom, or for every
+    /// denom if unset
+    #[returns(bool)]
+    Paused { denom: Option<String> },
+
+    /// Returns the latest rates of a denom as stored, both rates coming from
+    /// the same update
+    #[returns(crate::state::Rates)]
+    Rates { denom: String },
+
+    /// Returns the latest rates of up to 100 denoms, with an empty entry for
+    /// the denoms without rates
+    #[returns(LatestRatesResponse)]
+    RedemptionRates { denoms: Vec<String> },
+
+    /// Returns a page of the latest rates of the registered denoms, with an
+    /// empty entry for the denoms without rates
+    #[returns(LatestRatesResponse)]
+    AllLatestRates {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Returns the price of a denom, as its latest redemption rate times the
+    /// price of its underlying asset given by the price source. Fails if the
+    /// denom is paused, if its redemption rate is older than its maximum
+    /// staleness, which must be set, or if the underlying price is older than
+    /// the price source's maximum age
+    #[returns(PriceResponse)]
+    Price { denom: String },
+
+    #[returns(Option<crate::state::Aggregation>)]
+    Aggregation { denom: String },
+
+    /// Returns an aggregation round of a denom with its submissions, by
+    /// default the latest one. Only the last 100 rounds are kept
+    #[returns(Option<crate::state::AggregationRound>)]
+    AggregationRound { denom: String, round: Option<u64> },
+
     #[returns(RedemptionRateResponse)]
     RedemptionRate {
         denom: String,
         params: Option<Binary>,
     },
 
+    /// Returns the time-weighted average redemption rate over the last
+    /// `window_seconds`
+    #[returns(RedemptionRateTwapResponse)]
+    RedemptionRateTwap { denom: String, window_seconds: u64 },
+
+    /// Returns the redemption rate in effect at the given block height or time
+    #[returns(RedemptionRate)]
+    RedemptionRateAt { denom: String, at: PointInTime },
+
     #[returns(HistoricalRedemptionRatesResponse)]
     HistoricalRedemptionRates {
         denom: String,
         params: Option<Binary>,
+        /// Block height to start after, in the requested order
+        start_after: Option<u64>,
         limit: Option<u64>,
+        /// Defaults to the newest rates first
+        order: Option<OrderBy>,
     },
 
     #[returns(PurchaseRateResponse)]
@@ -40,34 +260,176 @@ pub enum QueryMsg {
         params: Option<Binary>,
     },
 
+    /// Returns the time-weighted average purchase rate over the last
+    /// `window_seconds`
+    #[returns(PurchaseRateTwapResponse)]
+    PurchaseRateTwap { denom: String, window_seconds: u64 },
+
+    /// Returns the purchase rate in effect at the given block height or time
+    #[returns(PurchaseRate)]
+    PurchaseRateAt { denom: String, at: PointInTime },
+
     #[returns(HistoricalPurchaseRatesResponse)]
     HistoricalPurchaseRates {
         denom: String,
         params: Option<Binary>,
+        /// Block height to start after, in the requested order
+        start_after: Option<u64>,
         limit: Option<u64>,
+        /// Defaults to the newest rates first
+        order: Option<OrderBy>,
     },
 }
 
+#[cw_serde]
+pub struct FeedersResponse {
+    pub feeders: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct DenomsResponse {
+    pub denoms: Vec<DenomInfo>,
+}
+
+#[cw_serde]
+pub struct PriceSourceInfo {
+    pub address: String,
+    /// Maximum age, in seconds, of the prices returned by the contract
+    pub max_price_age_seconds: u64,
+}
+
+#[cw_serde]
+pub struct DenomInfo {
+    pub denom: String,
+    pub metadata: DenomMetadata,
+}
+
+#[cw_serde]
+pub enum PointInTime {
+    /// Block height
+    Height(u64),
+    /// Unix timestamp
+    Time(u64),
+}
+
+#[cw_serde]
+pub enum OrderBy {
+    Ascending,
+    Descending,
+}
+
+impl From<OrderBy> for Order {
+    fn from(order: OrderBy) -> Self {
+        match order {
+            OrderBy::Ascending => Order::Ascending,
+            OrderBy::Descending => Order::Descending,
+        }
+    }
+}
+
+/// Options of the rate queries, JSON-encoded in their `params`, e.g.
+/// `{"v1": {"max_age_seconds": 3600, "on_stale": "error"}}`
+#[cw_serde]
+pub enum RateQueryParams {
+    V1(RateQueryParamsV1),
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RateQueryParamsV1 {
+    /// Maximum age, in seconds, of the rate. It can only tighten the denom's
+    /// maximum staleness, the stricter of the two applying
+    pub max_age_seconds: Option<u64>,
+    /// Minimum block height at which the rate must have been posted
+    pub min_block_height: Option<u64>,
+    /// What to do with a stale rate, defaults to flagging it
+    pub on_stale: Option<OnStale>,
+}
+
+#[cw_serde]
+pub enum OnStale {
+    /// Returns the rate flagged as stale
+    Flag,
+    /// Fails the query
+    Error,
+}
+
 #[cw_serde]
 pub struct RedemptionRateResponse {
     pub redemption_rate: Decimal,
     pub update_time: u64,
+    /// Block height at which the rate was posted
+    pub block_height: u64,
+    /// Address which posted the rate, or the contract itself for the median
+    /// of an aggregation round
+    pub feeder: Addr,
+    /// Unix timestamp of the observation on the host chain, if supplied
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain, if supplied
+    pub source_block_height: Option<u64>,
+    /// Whether the rate is older than the denom's maximum staleness or the
+    /// query's `max_age_seconds`, or was posted below its `min_block_height`
+    pub is_stale: bool,
+    /// Whether posting rates for the denom is paused
+    pub is_frozen: bool,
 }
 
 #[cw_serde]
 pub struct PurchaseRateResponse {
     pub purchase_rate: Decimal,
     pub update_time: u64,
+    /// Block height at which the rate was posted
+    pub block_height: u64,
+    /// Address which posted the rate, or the contract itself for the median
+    /// of an aggregation round
+    pub feeder: Addr,
+    /// Unix timestamp of the observation on the host chain, if supplied
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain, if supplied
+    pub source_block_height: Option<u64>,
+    /// Whether the rate is older than the denom's maximum staleness or the
+    /// query's `max_age_seconds`, or was posted below its `min_block_height`
+    pub is_stale: bool,
+    /// Whether posting rates for the denom is paused
+    pub is_frozen: bool,
+}
+
+#[cw_serde]
+pub struct RedemptionRateTwapResponse {
+    pub redemption_rate: Decimal,
+    /// Number of posted rates the average is computed from, a rate carried
+    /// over counting once with the update it was posted in
+    pub num_samples: u32,
+    /// Seconds of the window covered by the rates
+    pub span_seconds: u64,
+    /// Whether posting rates for the denom is paused
+    pub is_frozen: bool,
 }
 
 #[cw_serde]
 pub struct HistoricalRedemptionRatesResponse {
     pub redemption_rates: Vec<RedemptionRate>,
+    /// Block height to use as `start_after` to get the next page, if any
+    pub next_key: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PurchaseRateTwapResponse {
+    pub purchase_rate: Decimal,
+    /// Number of posted rates the average is computed from, a rate carried
+    /// over counting once with the update it was posted in
+    pub num_samples: u32,
+    /// Seconds of the window covered by the rates
+    pub span_seconds: u64,
+    /// Whether posting rates for the denom is paused
+    pub is_frozen: bool,
 }
 
 #[cw_serde]
 pub struct HistoricalPurchaseRatesResponse {
     pub purchase_rates: Vec<PurchaseRate>,
+    /// Block height to use as `start_after` to get the next page, if any
+    pub next_key: Option<u64>,
 }
 
 #[cw_serde]
@@ -75,6 +437,12 @@ pub struct RedemptionRate {
     pub denom: String,
     pub redemption_rate: Decimal,
     pub update_time: u64,
+    pub block_height: u64,
+    pub feeder: Addr,
+    pub source_update_time: Option<u64>,
+    pub source_block_height: Option<u64>,
+    /// Whether the update was flagged by the admin as a slashing event
+    pub slashing: bool,
 }
 
 #[cw_serde]
@@ -82,7 +450,59 @@ pub struct PurchaseRate {
     pub denom: String,
     pub purchase_rate: Decimal,
     pub update_time: u64,
+    pub block_height: u64,
+    pub feeder: Addr,
+    pub source_update_time: Option<u64>,
+    pub source_block_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct LatestRatesResponse {
+    pub rates: Vec<LatestRates>,
+}
+
+#[cw_serde]
+pub struct LatestRates {
+    pub denom: String,
+    /// `None` if no rates were posted for the denom
+    pub purchase_rate: Option<PurchaseRateResponse>,
+    /// `None` if no rates were posted for the denom
+    pub redemption_rate: Option<RedemptionRateResponse>,
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal,
+    pub redemption_rate: Decimal,
+    /// Unix timestamp of the redemption rate
+    pub redemption_rate_update_time: u64,
+    /// Price of the underlying asset
+    pub underlying_price: Decimal,
+    /// Unix timestamp of the underlying asset's price
+    pub underlying_price_update_time: u64,
+}
+
+/// Query interface of the price source
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum PriceSourceQueryMsg {
+    /// Returns the price of an asset, by its base denom
+    #[returns(PriceSourceResponse)]
+    Price { denom: String },
+}
+
+#[cw_serde]
+pub struct PriceSourceResponse {
+    pub price: Decimal,
+    /// Unix timestamp
+    pub update_time: u64,
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Feeders to register, e.g. the admin which posted the rates up to
+    /// v1.0.0
+    pub feeders: Option<Vec<String>>,
+    /// Denoms to register, e.g. the ones with rates posted up to v1.0.0
+    pub denoms: Option<Vec<DenomInfo>>,
+}
@@ -1,123 +1,666 @@
+use crate::error::QueryError;
 use crate::msg::{
-    HistoricalPurchaseRatesResponse, HistoricalRedemptionRatesResponse, PurchaseRate,
-    PurchaseRateResponse, RedemptionRate, RedemptionRateResponse,
+    DenomInfo, DenomsResponse, FeedersResponse, HistoricalPurchaseRatesResponse,
+    HistoricalRedemptionRatesResponse, LatestRates, LatestRatesResponse, OnStale, OrderBy,
+    PointInTime, PriceResponse, PriceSourceQueryMsg, PriceSourceResponse, PurchaseRate,
+    PurchaseRateResponse, PurchaseRateTwapResponse, RateQueryParams, RateQueryParamsV1,
+    RedemptionRate, RedemptionRateResponse, RedemptionRateTwapResponse,
 };
-use crate::state::{Rates, RATES};
-use cosmwasm_std::{Binary, Deps, Order, StdError, StdResult};
+use crate::state::{
+    AggregationRound, RateOrigin, Rates, Retention, AGGREGATION_ROUNDS, CONFIG, DENOMS, FEEDERS,
+    MAX_STALENESS, PAUSED, PAUSED_DENOMS, RATES, RATES_INDEX, RETENTION,
+};
+use cosmwasm_std::{from_json, Binary, Decimal, Decimal256, Deps, Env, Order, StdResult, Storage};
+use cw_storage_plus::Bound;
+use std::collections::BTreeSet;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
+pub fn query_feeders(deps: Deps) -> Result<FeedersResponse, QueryError> {
+    let feeders = FEEDERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(FeedersResponse { feeders })
+}
+
+pub fn query_denoms(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<DenomsResponse, QueryError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let denoms = DENOMS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|v| v.map(|(denom, metadata)| DenomInfo { denom, metadata }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DenomsResponse { denoms })
+}
+
+pub fn query_retention(deps: Deps, denom: String) -> Result<Retention, QueryError> {
+    Ok(RETENTION
+        .may_load(deps.storage, &denom)?
+        .unwrap_or_default())
+}
 
 pub fn query_purchase_rate(
     deps: Deps,
+    env: Env,
     denom: String,
     params: Option<Binary>,
-) -> StdResult<PurchaseRateResponse> {
-    if params.is_some() {
-        return Err(StdError::generic_err(
-            "invalid query request - params must be None",
-        ));
+) -> Result<PurchaseRateResponse, QueryError> {
+    let params = parse_params(params)?;
+
+    match get_latest_rates(deps, denom.clone())? {
+        Some(rates) => purchase_rate_response(deps, &env, &denom, &rates, &params),
+        None => Err(QueryError::NotFound {
+            denom,
+            rate: "purchase rate".to_string(),
+        }),
+    }
+}
+
+fn purchase_rate_response(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    rates: &Rates,
+    params: &RateQueryParamsV1,
+) -> Result<PurchaseRateResponse, QueryError> {
+    let origin = rates.purchase_rate_origin();
+    Ok(PurchaseRateResponse {
+        purchase_rate: rates.purchase_rate,
+        is_stale: check_stale(deps, env, denom, &origin, params, "purchase rate")?,
+        is_frozen: is_frozen(deps.storage, denom)?,
+        update_time: origin.update_time,
+        block_height: origin.block_height,
+        feeder: origin.feeder,
+        source_update_time: origin.source_update_time,
+        source_block_height: origin.source_block_height,
+    })
+}
+
+pub fn query_purchase_rate_at(
+    deps: Deps,
+    denom: String,
+    at: PointInTime,
+) -> Result<PurchaseRate, QueryError> {
+    match get_rates_at(deps, &denom, &at)? {
+        Some(rates) => {
+            let origin = rates.purchase_rate_origin();
+            Ok(PurchaseRate {
+                denom,
+                purchase_rate: rates.purchase_rate,
+                update_time: origin.update_time,
+                block_height: origin.block_height,
+                feeder: origin.feeder,
+                source_update_time: origin.source_update_time,
+                source_block_height: origin.source_block_height,
+            })
+        }
+        None => Err(rates_at_not_found(deps, denom, "purchase rate")?),
     }
+}
 
-    match get_latest_rates(deps, denom)? {
-        Some(rates) => Ok(PurchaseRateResponse {
-            purchase_rate: rates.purchase_rate,
-            update_time: rates.update_time,
+pub fn query_purchase_rate_twap(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    window_seconds: u64,
+) -> Result<PurchaseRateTwapResponse, QueryError> {
+    match get_twap(
+        deps,
+        &env,
+        &denom,
+        window_seconds,
+        |rates| rates.purchase_rate,
+        Rates::purchase_rate_origin,
+    )? {
+        Some(twap) => Ok(PurchaseRateTwapResponse {
+            purchase_rate: twap.average,
+            num_samples: twap.num_samples,
+            span_seconds: twap.span_seconds,
+            is_frozen: is_frozen(deps.storage, &denom)?,
+        }),
+        None => Err(QueryError::NotFound {
+            denom,
+            rate: "purchase rate".to_string(),
         }),
-        None => Err(StdError::generic_err("purchase rate not found")),
     }
 }
 
 pub fn query_historical_purchase_rates(
     deps: Deps,
+    env: Env,
     denom: String,
     params: Option<Binary>,
+    start_after: Option<u64>,
     limit: Option<u64>,
-) -> StdResult<HistoricalPurchaseRatesResponse> {
-    if params.is_some() {
-        return Err(StdError::generic_err(
-            "invalid query request - params must be None",
-        ));
-    }
+    order: Option<OrderBy>,
+) -> Result<HistoricalPurchaseRatesResponse, QueryError> {
+    let params = parse_params(params)?;
 
-    let historical_rates = get_historical_rates(deps, denom.clone(), limit)?;
+    // Only the updates in which the purchase rate was posted, within the
+    // options' maximum age and minimum block height
+    let (historical_rates, next_key) =
+        get_historical_rates(deps, denom.clone(), start_after, limit, order, |rates| {
+            rates.purchase_rate_carried_from.is_none() && !is_outside_params(&env, rates, &params)
+        })?;
 
     Ok(HistoricalPurchaseRatesResponse {
         purchase_rates: historical_rates
             .iter()
-            .map(|v| PurchaseRate {
+            .map(|(_, v)| PurchaseRate {
                 denom: denom.clone(),
                 purchase_rate: v.purchase_rate,
                 update_time: v.update_time,
+                block_height: v.block_height,
+                feeder: v.feeder.clone(),
+                source_update_time: v.source_update_time,
+                source_block_height: v.source_block_height,
             })
             .collect(),
+        next_key,
     })
 }
 
 pub fn query_redemption_rate(
     deps: Deps,
+    env: Env,
     denom: String,
     params: Option<Binary>,
-) -> StdResult<RedemptionRateResponse> {
-    if params.is_some() {
-        return Err(StdError::generic_err(
-            "invalid query request - params must be None",
-        ));
+) -> Result<RedemptionRateResponse, QueryError> {
+    let params = parse_params(params)?;
+
+    match get_latest_rates(deps, denom.clone())? {
+        Some(rates) => redemption_rate_response(deps, &env, &denom, &rates, &params),
+        None => Err(QueryError::NotFound {
+            denom,
+            rate: "redemption rate".to_string(),
+        }),
+    }
+}
+
+fn redemption_rate_response(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    rates: &Rates,
+    params: &RateQueryParamsV1,
+) -> Result<RedemptionRateResponse, QueryError> {
+    let origin = rates.redemption_rate_origin();
+    Ok(RedemptionRateResponse {
+        redemption_rate: rates.redemption_rate,
+        is_stale: check_stale(deps, env, denom, &origin, params, "redemption rate")?,
+        is_frozen: is_frozen(deps.storage, denom)?,
+        update_time: origin.update_time,
+        block_height: origin.block_height,
+        feeder: origin.feeder,
+        source_update_time: origin.source_update_time,
+        source_block_height: origin.source_block_height,
+    })
+}
+
+pub fn query_rates(deps: Deps, denom: String) -> Result<Rates, QueryError> {
+    get_latest_rates(deps, denom.clone())?.ok_or(QueryError::NotFound {
+        denom,
+        rate: "rates".to_string(),
+    })
+}
+
+pub fn query_redemption_rates(
+    deps: Deps,
+    env: Env,
+    denoms: Vec<String>,
+) -> Result<LatestRatesResponse, QueryError> {
+    if denoms.len() > MAX_LIMIT as usize {
+        return Err(QueryError::InvalidParams {
+            reason: format!("at most {MAX_LIMIT} denoms"),
+        });
+    }
+
+    let rates = denoms
+        .into_iter()
+        .map(|denom| get_latest_rates_entry(deps, &env, denom))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(LatestRatesResponse { rates })
+}
+
+pub fn query_all_latest_rates(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<LatestRatesResponse, QueryError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let rates = DENOMS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|denom| get_latest_rates_entry(deps, &env, denom?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(LatestRatesResponse { rates })
+}
+
+/// Latest rates of a denom, empty if no rates were posted for it
+fn get_latest_rates_entry(deps: Deps, env: &Env, denom: String) -> Result<LatestRates, QueryError> {
+    let params = RateQueryParamsV1::default();
+    let (purchase_rate, redemption_rate) = match get_latest_rates(deps, denom.clone())? {
+        Some(rates) => (
+            Some(purchase_rate_response(deps, env, &denom, &rates, &params)?),
+            Some(redemption_rate_response(
+                deps, env, &denom, &rates, &params,
+            )?),
+        ),
+        None => (None, None),
+    };
+
+    Ok(LatestRates {
+        denom,
+        purchase_rate,
+        redemption_rate,
+    })
+}
+
+pub fn query_price(deps: Deps, env: Env, denom: String) -> Result<PriceResponse, QueryError> {
+    let price_source = CONFIG
+        .load(deps.storage)?
+        .price_source
+        .ok_or(QueryError::PriceSourceNotConfigured {})?;
+    let metadata =
+        DENOMS
+            .may_load(deps.storage, &denom)?
+            .ok_or_else(|| QueryError::DenomNotRegistered {
+                denom: denom.clone(),
+            })?;
+    // Rates of a paused denom may be under investigation, so are not priced
+    if is_frozen(deps.storage, &denom)? {
+        return Err(QueryError::Paused { denom });
+    }
+
+    let rates = get_latest_rates(deps, denom.clone())?.ok_or_else(|| QueryError::NotFound {
+        denom: denom.clone(),
+        rate: "redemption rate".to_string(),
+    })?;
+    // Prices are only served for denoms whose rates are bounded in age
+    let max_staleness = MAX_STALENESS
+        .may_load(deps.storage, &denom)?
+        .ok_or_else(|| QueryError::MaxStalenessNotConfigured {
+            denom: denom.clone(),
+        })?;
+    let now = env.block.time.seconds();
+    let redemption_rate_update_time = rates.redemption_rate_origin().update_time;
+    if now.saturating_sub(redemption_rate_update_time) > max_staleness {
+        return Err(QueryError::Stale {
+            denom,
+            rate: "redemption rate".to_string(),
+        });
+    }
+
+    let underlying: PriceSourceResponse = deps.querier.query_wasm_smart(
+        price_source.address,
+        &PriceSourceQueryMsg::Price {
+            denom: metadata.base_denom,
+        },
+    )?;
+    if now.saturating_sub(underlying.update_time) > price_source.max_price_age_seconds {
+        return Err(QueryError::Stale {
+            denom,
+            rate: "underlying price".to_string(),
+        });
     }
 
-    match get_latest_rates(deps, denom)? {
-        Some(rates) => Ok(RedemptionRateResponse {
-            redemption_rate: rates.redemption_rate,
-            update_time: rates.update_time,
+    Ok(PriceResponse {
+        price: rates
+            .redemption_rate
+            .checked_mul(underlying.price)
+            .map_err(|_| QueryError::Overflow {
+                value: "price".to_string(),
+            })?,
+        redemption_rate: rates.redemption_rate,
+        redemption_rate_update_time,
+        underlying_price: underlying.price,
+        underlying_price_update_time: underlying.update_time,
+    })
+}
+
+pub fn query_redemption_rate_at(
+    deps: Deps,
+    denom: String,
+    at: PointInTime,
+) -> Result<RedemptionRate, QueryError> {
+    match get_rates_at(deps, &denom, &at)? {
+        Some(rates) => {
+            let origin = rates.redemption_rate_origin();
+            Ok(RedemptionRate {
+                denom,
+                redemption_rate: rates.redemption_rate,
+                update_time: origin.update_time,
+                block_height: origin.block_height,
+                feeder: origin.feeder,
+                source_update_time: origin.source_update_time,
+                source_block_height: origin.source_block_height,
+                slashing: origin.slashing,
+            })
+        }
+        None => Err(rates_at_not_found(deps, denom, "redemption rate")?),
+    }
+}
+
+pub fn query_redemption_rate_twap(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    window_seconds: u64,
+) -> Result<RedemptionRateTwapResponse, QueryError> {
+    match get_twap(
+        deps,
+        &env,
+        &denom,
+        window_seconds,
+        |rates| rates.redemption_rate,
+        Rates::redemption_rate_origin,
+    )? {
+        Some(twap) => Ok(RedemptionRateTwapResponse {
+            redemption_rate: twap.average,
+            num_samples: twap.num_samples,
+            span_seconds: twap.span_seconds,
+            is_frozen: is_frozen(deps.storage, &denom)?,
+        }),
+        None => Err(QueryError::NotFound {
+            denom,
+            rate: "redemption rate".to_string(),
         }),
-        None => Err(StdError::generic_err("redemption rate not found")),
     }
 }
 
 pub fn query_historical_redemption_rates(
     deps: Deps,
+    env: Env,
     denom: String,
     params: Option<Binary>,
+    start_after: Option<u64>,
     limit: Option<u64>,
-) -> StdResult<HistoricalRedemptionRatesResponse> {
-    if params.is_some() {
-        return Err(StdError::generic_err(
-            "invalid query request - params must be None",
-        ));
-    }
+    order: Option<OrderBy>,
+) -> Result<HistoricalRedemptionRatesResponse, QueryError> {
+    let params = parse_params(params)?;
 
-    let historical_rates = get_historical_rates(deps, denom.clone(), limit)?;
+    // Only the updates in which the redemption rate was posted, within the
+    // options' maximum age and minimum block height
+    let (historical_rates, next_key) =
+        get_historical_rates(deps, denom.clone(), start_after, limit, order, |rates| {
+            rates.redemption_rate_carried_from.is_none() && !is_outside_params(&env, rates, &params)
+        })?;
 
     Ok(HistoricalRedemptionRatesResponse {
         redemption_rates: historical_rates
             .iter()
-            .map(|v| RedemptionRate {
+            .map(|(_, v)| RedemptionRate {
                 denom: denom.clone(),
                 redemption_rate: v.redemption_rate,
                 update_time: v.update_time,
+                block_height: v.block_height,
+                feeder: v.feeder.clone(),
+                source_update_time: v.source_update_time,
+                source_block_height: v.source_block_height,
+                slashing: v.slashing,
             })
             .collect(),
+        next_key,
     })
 }
 
-pub fn get_latest_rates(deps: Deps, denom: String) -> StdResult<Option<Rates>> {
-    RATES
-        .prefix(&denom)
-        .range(deps.storage, None, None, Order::Descending)
-        .take(1)
+pub fn query_aggregation_round(
+    deps: Deps,
+    denom: String,
+    round: Option<u64>,
+) -> Result<Option<AggregationRound>, QueryError> {
+    Ok(match round {
+        Some(round) => AGGREGATION_ROUNDS.may_load(deps.storage, (&denom, round))?,
+        None => get_latest_round(deps.storage, &denom)?,
+    })
+}
+
+pub fn get_latest_round(storage: &dyn Storage, denom: &str) -> StdResult<Option<AggregationRound>> {
+    AGGREGATION_ROUNDS
+        .prefix(denom)
+        .range(storage, None, None, Order::Descending)
         .next()
         .transpose()
-        .map(|v| v.map(|(_, rates)| rates))
+        .map(|v| v.map(|(_, round)| round))
+}
+
+pub fn get_latest_rates(deps: Deps, denom: String) -> StdResult<Option<Rates>> {
+    match RATES_INDEX.may_load(deps.storage, &denom)? {
+        Some(index) => RATES.may_load(deps.storage, (&denom, index.newest)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the most recent rates posted at or before the given point
+pub fn get_rates_at(deps: Deps, denom: &str, at: &PointInTime) -> StdResult<Option<Rates>> {
+    let prefix = RATES.prefix(denom);
+    match *at {
+        PointInTime::Height(height) => prefix
+            .range(
+                deps.storage,
+                None,
+                Some(Bound::inclusive(height)),
+                Order::Descending,
+            )
+            .next()
+            .transpose()
+            .map(|v| v.map(|(_, rates)| rates)),
+        PointInTime::Time(time) => {
+            for item in prefix.range(deps.storage, None, None, Order::Descending) {
+                let (_, rates) = item?;
+                if rates.update_time <= time {
+                    return Ok(Some(rates));
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Error returned when no rates were in effect at the requested point
+fn rates_at_not_found(deps: Deps, denom: String, rate: &str) -> StdResult<QueryError> {
+    Ok(match get_latest_rates(deps, denom.clone())? {
+        Some(_) => QueryError::PredatesHistory { denom },
+        None => QueryError::NotFound {
+            denom,
+            rate: rate.to_string(),
+        },
+    })
 }
 
+pub struct Twap {
+    pub average: Decimal,
+    pub num_samples: u32,
+    pub span_seconds: u64,
+}
+
+/// Computes the time-weighted average of a rate over the last `window_seconds`,
+/// each rate being weighted by the time it stayed in effect within the window.
+/// Rates carried over count as a single sample with the update they were
+/// posted in
+pub fn get_twap(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    window_seconds: u64,
+    rate: fn(&Rates) -> Decimal,
+    origin: fn(&Rates) -> RateOrigin,
+) -> Result<Option<Twap>, QueryError> {
+    if window_seconds == 0 {
+        return Err(QueryError::InvalidParams {
+            reason: "window_seconds must be greater than zero".to_string(),
+        });
+    }
+
+    let now = env.block.time.seconds();
+    let window_start = now.saturating_sub(window_seconds);
+
+    let mut weighted_sum = Decimal256::zero();
+    // Block heights of the updates the rates were posted in
+    let mut origins = BTreeSet::new();
+    let mut latest = None;
+    // End of the period in which the current rates are in effect
+    let mut end = now;
+    for item in RATES
+        .prefix(denom)
+        .range(deps.storage, None, None, Order::Descending)
+    {
+        let (_, rates) = item?;
+        let start = rates.update_time.clamp(window_start, end);
+        weighted_sum += Decimal256::from(rate(&rates)) * Decimal256::from_ratio(end - start, 1u64);
+        origins.insert(origin(&rates).block_height);
+        latest = latest.or(Some(rate(&rates)));
+        end = start;
+
+        // The remaining rates were replaced before the window started
+        if rates.update_time <= window_start {
+            break;
+        }
+    }
+
+    let latest = match latest {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+    let span_seconds = now - end;
+    let average = if span_seconds == 0 {
+        latest
+    } else {
+        (weighted_sum / Decimal256::from_ratio(span_seconds, 1u64))
+            .try_into()
+            .map_err(|_| QueryError::Overflow {
+                value: "time-weighted average".to_string(),
+            })?
+    };
+
+    Ok(Some(Twap {
+        average,
+        num_samples: origins.len() as u32,
+        span_seconds,
+    }))
+}
+
+/// Returns whether rates updated at `update_time` exceed the denom's maximum staleness
+pub fn is_stale(deps: Deps, env: &Env, denom: &str, update_time: u64) -> StdResult<bool> {
+    Ok(MAX_STALENESS
+        .may_load(deps.storage, denom)?
+        .is_some_and(|max_staleness| {
+            env.block.time.seconds().saturating_sub(update_time) > max_staleness
+        }))
+}
+
+/// Whether posting rates for the denom is paused, globally or for the denom
+pub fn is_frozen(storage: &dyn Storage, denom: &str) -> StdResult<bool> {
+    Ok(PAUSED.may_load(storage)?.unwrap_or(false) || PAUSED_DENOMS.has(storage, denom))
+}
+
+pub fn query_paused(deps: Deps, denom: Option<String>) -> Result<bool, QueryError> {
+    match denom {
+        Some(denom) => Ok(is_frozen(deps.storage, &denom)?),
+        None => Ok(PAUSED.may_load(deps.storage)?.unwrap_or(false)),
+    }
+}
+
+/// Decodes the options of a rate query, `None` keeping the defaults
+fn parse_params(params: Option<Binary>) -> Result<RateQueryParamsV1, QueryError> {
+    match params {
+        Some(params) => match from_json(&params) {
+            Ok(RateQueryParams::V1(params)) => Ok(params),
+            Err(_) => Err(QueryError::InvalidParams {
+                reason: "params must be None or valid rate query params".to_string(),
+            }),
+        },
+        None => Ok(RateQueryParamsV1::default()),
+    }
+}
+
+/// Whether the rates are older than the options' maximum age or were posted
+/// below their minimum block height
+fn is_outside_params(env: &Env, rates: &Rates, params: &RateQueryParamsV1) -> bool {
+    let too_old = params.max_age_seconds.is_some_and(|max_age_seconds| {
+        env.block.time.seconds().saturating_sub(rates.update_time) > max_age_seconds
+    });
+    let too_low = params
+        .min_block_height
+        .is_some_and(|min_block_height| rates.block_height < min_block_height);
+    too_old || too_low
+}
+
+/// Whether a rate posted in the given update is stale, under the stricter of
+/// the options' maximum age and the denom's maximum staleness. Fails if the
+/// options ask to
+fn check_stale(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    origin: &RateOrigin,
+    params: &RateQueryParamsV1,
+    rate: &str,
+) -> Result<bool, QueryError> {
+    let stale = is_stale(deps, env, denom, origin.update_time)?
+        || params.max_age_seconds.is_some_and(|max_age_seconds| {
+            env.block.time.seconds().saturating_sub(origin.update_time) > max_age_seconds
+        })
+        || params
+            .min_block_height
+            .is_some_and(|min_block_height| origin.block_height < min_block_height);
+
+    if stale && params.on_stale == Some(OnStale::Error) {
+        return Err(QueryError::Stale {
+            denom: denom.to_string(),
+            rate: rate.to_string(),
+        });
+    }
+    Ok(stale)
+}
+
+/// Page of rates with their block height, and the block height to start the
+/// next page after if there are more rates matching the filter
+type RatesPage = (Vec<(u64, Rates)>, Option<u64>);
+
 pub fn get_historical_rates(
     deps: Deps,
     denom: String,
+    start_after: Option<u64>,
     limit: Option<u64>,
-) -> StdResult<Vec<Rates>> {
-    // If limit is not specified, use usize::MAX to get all rates
-    let limit: usize = limit.unwrap_or(u64::MAX).try_into().unwrap_or(usize::MAX);
-    RATES
+    order: Option<OrderBy>,
+    filter: impl Fn(&Rates) -> bool,
+) -> StdResult<RatesPage> {
+    // A page holds at least one rate, for the next key to follow it
+    let limit = limit
+        .unwrap_or(DEFAULT_LIMIT.into())
+        .clamp(1, MAX_LIMIT.into()) as usize;
+    let order: Order = order.unwrap_or(OrderBy::Descending).into();
+    let (min, max) = match order {
+        Order::Ascending => (start_after.map(Bound::exclusive), None),
+        Order::Descending => (None, start_after.map(Bound::exclusive)),
+    };
+
+    // Load one more item to know whether there is a next page
+    let mut rates = RATES
         .prefix(&denom)
-        .range(deps.storage, None, None, Order::Descending)
-        .take(limit)
-        .map(|v| v.map(|(_, rates)| rates))
-        .collect()
+        .range(deps.storage, min, max, order)
+        .filter(|v| v.as_ref().map_or(true, |(_, rates)| filter(rates)))
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_key = if rates.len() > limit {
+        rates.truncate(limit);
+        rates.last().map(|(block_height, _)| *block_height)
+    } else {
+        None
+    };
+
+    Ok((rates, next_key))
 }
@@ -1,17 +1,85 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal};
+use cosmwasm_std::{Addr, Decimal, Empty};
 use cw_storage_plus::{Item, Map};
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const MAX_NUM_HISTORICAL_RATES: usize = 100;
+/// Admin proposed through `ProposeAdmin`, waiting to accept the ownership
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+
+/// Whether posting rates is paused for every denom
+pub const PAUSED: Item<bool> = Item::new("paused");
+
+/// Denoms for which posting rates is paused
+pub const PAUSED_DENOMS: Map<&str, Empty> = Map::new("paused_denoms");
+
+/// Addresses allowed to post rates
+pub const FEEDERS: Map<&Addr, Empty> = Map::new("feeders");
+
+/// Denoms accepted by `PostRates`, with their metadata
+pub const DENOMS: Map<&str, DenomMetadata> = Map::new("denoms");
+
+/// Number of rates kept for denoms without a retention policy
+pub const DEFAULT_MAX_NUM_HISTORICAL_RATES: u32 = 100;
+
+/// How long the rates history of a denom is kept
+pub const RETENTION: Map<&str, Retention> = Map::new("retention");
 
 /// Rates are stored with the denom and the block height
 pub const RATES: Map<(&str, u64), Rates> = Map::new("rates");
 
+/// Bookkeeping of the rates history of a denom, to post and prune rates at a
+/// constant cost
+pub const RATES_INDEX: Map<&str, RatesIndex> = Map::new("rates_index");
+
+/// Latest host chain observation supplied with the stored rates of a denom,
+/// which every newly supplied one must be newer than
+pub const LATEST_SOURCES: Map<&str, SourceObservation> = Map::new("latest_sources");
+
+/// Maximum rate changes accepted for a denom
+pub const DEVIATION_LIMITS: Map<&str, DeviationLimits> = Map::new("deviation_limits");
+
+/// Maximum age, in seconds, after which the latest rates of a denom are stale
+pub const MAX_STALENESS: Map<&str, u64> = Map::new("max_staleness");
+
+/// Rate policies enforced for a denom
+pub const RATE_POLICIES: Map<&str, RatePolicy> = Map::new("rate_policies");
+
+/// Out-of-band rates waiting for the admin's confirmation, one per denom
+pub const QUARANTINED_RATES: Map<&str, QuarantinedRates> = Map::new("quarantined_rates");
+
+/// Denoms whose rates are aggregated from the submissions of multiple feeders
+pub const AGGREGATIONS: Map<&str, Aggregation> = Map::new("aggregations");
+
+/// Number of aggregation rounds kept per denom
+pub const MAX_NUM_AGGREGATION_ROUNDS: u64 = 100;
+
+/// Aggregation rounds are stored with the denom and the round id
+pub const AGGREGATION_ROUNDS: Map<(&str, u64), AggregationRound> = Map::new("aggregation_rounds");
+
 #[cw_serde]
 pub struct Config {
     pub admin_address: Addr,
+    /// Sanity ceiling applied to every posted rate
+    pub max_rate: Option<Decimal>,
+    /// Address allowed to pause, but not unpause, posting rates
+    pub guardian: Option<Addr>,
+    /// Contract queried for the prices of the underlying assets
+    pub price_source: Option<PriceSource>,
+}
+
+#[cw_serde]
+pub struct PriceSource {
+    pub address: Addr,
+    /// Maximum age, in seconds, of the prices returned by the contract
+    pub max_price_age_seconds: u64,
+}
+
+#[cw_serde]
+pub struct PendingAdmin {
+    pub address: Addr,
+    /// Unix timestamp after which the proposal can no longer be accepted
+    pub expiry: Option<u64>,
 }
 
 #[cw_serde]
@@ -20,4 +88,165 @@ pub struct Rates {
     pub redemption_rate: Decimal,
     /// Unix timestamp
     pub update_time: u64,
+    /// Block height at which the rates took effect
+    pub block_height: u64,
+    /// Address which posted the rates, or the contract itself for the median
+    /// of an aggregation round
+    pub feeder: Addr,
+    /// Unix timestamp of the observation on the host chain, as supplied by
+    /// the feeder
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain, as supplied by the
+    /// feeder
+    pub source_block_height: Option<u64>,
+    /// Whether the update was flagged by the admin as a slashing event
+    pub slashing: bool,
+    /// Update the purchase rate was carried over from, if it was not posted
+    /// in this update
+    pub purchase_rate_carried_from: Option<RateOrigin>,
+    /// Update the redemption rate was carried over from, if it was not
+    /// posted in this update
+    pub redemption_rate_carried_from: Option<RateOrigin>,
+}
+
+impl Rates {
+    /// Update in which the purchase rate was posted
+    pub fn purchase_rate_origin(&self) -> RateOrigin {
+        self.purchase_rate_carried_from
+            .clone()
+            .unwrap_or_else(|| self.origin())
+    }
+
+    /// Update in which the redemption rate was posted
+    pub fn redemption_rate_origin(&self) -> RateOrigin {
+        self.redemption_rate_carried_from
+            .clone()
+            .unwrap_or_else(|| self.origin())
+    }
+
+    fn origin(&self) -> RateOrigin {
+        RateOrigin {
+            update_time: self.update_time,
+            block_height: self.block_height,
+            feeder: self.feeder.clone(),
+            source_update_time: self.source_update_time,
+            source_block_height: self.source_block_height,
+            slashing: self.slashing,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RateOrigin {
+    /// Unix timestamp
+    pub update_time: u64,
+    pub block_height: u64,
+    pub feeder: Addr,
+    /// Unix timestamp of the observation on the host chain
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain
+    pub source_block_height: Option<u64>,
+    /// Whether the update was flagged by the admin as a slashing event
+    pub slashing: bool,
+}
+
+#[cw_serde]
+pub struct DenomMetadata {
+    /// Denom of the underlying asset on the host chain, e.g. utia
+    pub base_denom: String,
+    pub host_chain_id: String,
+    pub decimals: u8,
+    /// Symbol to display, e.g. milkTIA
+    pub display_symbol: String,
+}
+
+#[cw_serde]
+pub struct RatesIndex {
+    /// Number of rates stored
+    pub count: u64,
+    /// Block height of the oldest rates stored
+    pub oldest: u64,
+    /// Block height of the newest rates stored
+    pub newest: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct SourceObservation {
+    /// Unix timestamp of the observation on the host chain
+    pub update_time: Option<u64>,
+    /// Block height of the observation on the host chain
+    pub block_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct Retention {
+    /// Maximum number of rates kept
+    pub max_count: Option<u32>,
+    /// Maximum age, in seconds, of the rates kept. The latest rates are
+    /// always kept
+    pub max_age_seconds: Option<u64>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            max_count: Some(DEFAULT_MAX_NUM_HISTORICAL_RATES),
+            max_age_seconds: None,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct DeviationLimits {
+    /// Maximum relative change against the latest rates, e.g. 0.05 for 5%
+    pub max_change_per_update: Option<Decimal>,
+    /// Maximum relative change against any rates posted in the last `window_seconds`
+    pub max_change_per_window: Option<Decimal>,
+    pub window_seconds: u64,
+}
+
+#[cw_serde]
+pub struct RatePolicy {
+    /// Rejects redemption rate decreases, unless flagged as a slashing event
+    pub non_decreasing_redemption_rate: bool,
+}
+
+#[cw_serde]
+pub struct QuarantinedRates {
+    /// Rates as posted, with the block height and the feeder they were
+    /// posted at and by
+    pub rates: Rates,
+}
+
+#[cw_serde]
+pub struct Aggregation {
+    /// Number of feeder submissions needed to finalize a round
+    pub quorum: u32,
+    /// Time, in seconds, from the first submission of a round within which
+    /// the quorum has to be reached
+    pub window_seconds: u64,
+}
+
+#[cw_serde]
+pub struct AggregationRound {
+    pub id: u64,
+    /// Unix timestamp of the first submission
+    pub start_time: u64,
+    pub submissions: Vec<Submission>,
+    /// Whether the median of the submissions was stored
+    pub finalized: bool,
+}
+
+#[cw_serde]
+pub struct Submission {
+    pub feeder: Addr,
+    pub purchase_rate: Decimal,
+    pub redemption_rate: Decimal,
+    /// Unix timestamp
+    pub update_time: u64,
+    /// Unix timestamp of the observation on the host chain
+    pub source_update_time: Option<u64>,
+    /// Block height of the observation on the host chain
+    pub source_block_height: Option<u64>,
 }
@@ -0,0 +1,108 @@
+use crate::error::ContractError;
+use crate::state::{Aggregation, DenomMetadata, Retention};
+use cosmwasm_std::{ensure, Addr, Api, Decimal};
+use std::str::FromStr;
+
+/// Parses a rate posted by a feeder, rejecting malformed strings, zero
+/// and values above the configured ceiling
+pub fn parse_rate(
+    field: &str,
+    value: &str,
+    max_rate: Option<Decimal>,
+) -> Result<Decimal, ContractError> {
+    let rate = Decimal::from_str(value).map_err(|_| ContractError::InvalidRate {
+        field: field.to_string(),
+        value: value.to_string(),
+    })?;
+    ensure!(
+        !rate.is_zero(),
+        ContractError::ZeroRate {
+            field: field.to_string()
+        }
+    );
+    if let Some(max_rate) = max_rate {
+        ensure!(
+            rate <= max_rate,
+            ContractError::RateAboveCeiling {
+                field: field.to_string(),
+                value: rate,
+                max_rate,
+            }
+        );
+    }
+
+    Ok(rate)
+}
+
+/// Validates the sanity ceiling applied to every posted rate
+pub fn validate_max_rate(max_rate: Option<Decimal>) -> Result<(), ContractError> {
+    ensure!(
+        max_rate != Some(Decimal::zero()),
+        ContractError::ZeroRate {
+            field: "max_rate".to_string()
+        }
+    );
+    Ok(())
+}
+
+/// Validates a denom and the metadata it is registered with
+pub fn validate_denom_metadata(denom: &str, metadata: &DenomMetadata) -> Result<(), ContractError> {
+    for (field, value) in [
+        ("denom", denom),
+        ("base_denom", &metadata.base_denom),
+        ("host_chain_id", &metadata.host_chain_id),
+        ("display_symbol", &metadata.display_symbol),
+    ] {
+        ensure!(
+            !value.trim().is_empty(),
+            ContractError::InvalidDenomMetadata {
+                field: field.to_string()
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Validates that a retention policy bounds the history and keeps at least
+/// the latest rates
+pub fn validate_retention(retention: &Retention) -> Result<(), ContractError> {
+    ensure!(
+        retention.max_count.is_some() || retention.max_age_seconds.is_some(),
+        ContractError::InvalidRetention {
+            reason: "max_count or max_age_seconds must be set".to_string()
+        }
+    );
+    ensure!(
+        retention.max_count != Some(0),
+        ContractError::InvalidRetention {
+            reason: "max_count must be greater than zero".to_string()
+        }
+    );
+    Ok(())
+}
+
+/// Validates that an aggregation needs at least one submission and leaves
+/// time to reach the quorum
+pub fn validate_aggregation(aggregation: &Aggregation) -> Result<(), ContractError> {
+    ensure!(
+        aggregation.quorum > 0,
+        ContractError::InvalidAggregation {
+            reason: "quorum must be greater than zero".to_string()
+        }
+    );
+    ensure!(
+        aggregation.window_seconds > 0,
+        ContractError::InvalidAggregation {
+            reason: "window_seconds must be greater than zero".to_string()
+        }
+    );
+    Ok(())
+}
+
+/// Validates an address given in a message
+pub fn validate_address(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: address.to_string(),
+        })
+}